@@ -3,23 +3,272 @@
 
 /* auto-generated by NAPI-RS */
 
+export const enum SocketProtocol {
+  Pair0 = 'pair0',
+  Pair1 = 'pair1',
+  Req = 'req',
+  Rep = 'rep',
+  Pub = 'pub',
+  Sub = 'sub',
+  Push = 'push',
+  Pull = 'pull',
+  Surveyor = 'surveyor',
+  Respondent = 'respondent',
+  Bus = 'bus'
+}
+export const enum QueuePolicy {
+  Block = 'block',
+  DropOldest = 'dropOldest',
+  DropNewest = 'dropNewest',
+  Error = 'error'
+}
 export interface SocketOptions {
   recvTimeout?: number
   sendTimeout?: number
+  /** 协议类型，默认 pair1 */
+  protocol?: SocketProtocol
+  /** Req 协议下未收到回复时自动重发请求的间隔（毫秒） */
+  resendInterval?: number
+  /** Surveyor 协议下默认的调查截止时间（毫秒） */
+  surveyTime?: number
+  /** 是否启用心跳（recvMessage / onMessage 接收期间定时发送 ping 并检测对端是否存活） */
+  enableHeartbeat?: boolean
+  /** 心跳间隔（毫秒），默认 3000 */
+  heartbeatInterval?: number
+  /** 连续多少个心跳间隔没有收到任何消息时判定连接断开，默认 3 */
+  heartbeatMaxMissed?: number
+  /** ping 消息内容，默认 "__nng_ping__" */
+  heartbeatPayload?: string
+  /** 自动回复的 pong 消息内容，默认 "__nng_pong__" */
+  heartbeatReplyPayload?: string
+  /** 收到对端的 ping 时是否自动回复 pong（不需要启用心跳也可以单独开启） */
+  heartbeatAutoReply?: boolean
+  /** recvMessage 的自动重连策略，不设置时连接出错后停止接收 */
+  reconnect?: ReconnectOptions
+  /** nng 底层连接断开后重新连接的最短间隔（毫秒），nng 默认 100 */
+  reconnectMinTime?: number
+  /** nng 底层重连间隔指数增长的上限（毫秒），nng 默认 0（不增长，始终使用 reconnectMinTime） */
+  reconnectMaxTime?: number
+  /** connect / recvMessage 时不等待连接建立，对端未启动时在后台按重连间隔继续尝试 */
+  nonBlockingDial?: boolean
+  /** recvMessage / onMessage 的回调额外收到带连接信息的 ReceivedMessage */
+  messageMetadata?: boolean
+  /** 透明压缩：发送时自动压缩、接收时自动解压，通信双方都需要开启 */
+  compression?: CompressionOptions
+  /** recvMessage / onMessage 最多积压多少条还没交给回调的消息，默认不限制；messages() 的缓存条数，默认 16 */
+  maxQueueSize?: number
+  /** 积压达到 maxQueueSize 时的处理方式，默认 block */
+  queuePolicy?: QueuePolicy
+  /** ipc:// 地址的 socket 文件权限、残留文件清理以及按对端用户过滤连接 */
+  ipc?: IpcOptions
+  /** tls+tcp:// 和 wss:// 地址的证书配置，需要以 tls feature 编译 */
+  tls?: TlsOptions
+  /** ws:// 和 wss:// 地址的握手 HTTP 头、路径和帧类型 */
+  webSocket?: WebSocketOptions
+  /** Pair1 协议下允许同时连接多个对端，配合 sendTo 回复指定的对端 */
+  polyamorous?: boolean
+}
+export interface ReconnectOptions {
+  /** 第一次重连前的等待时间（毫秒），默认 100 */
+  initialDelay?: number
+  /** 指数退避的最长等待时间（毫秒），默认 30000 */
+  maxDelay?: number
+  /** 等待时间的随机浮动比例（0 ~ 1），默认 0.2 */
+  jitter?: number
+  /** 最多连续重连的次数，不设置时一直重连 */
+  maxAttempts?: number
+}
+export const enum ReconnectState {
+  Connecting = 'connecting',
+  Connected = 'connected',
+  Reconnecting = 'reconnecting',
+  GaveUp = 'gave-up'
+}
+export interface ReconnectStatus {
+  state: ReconnectState
+  /** 连续失败的次数，connected 时为这次连接成功前失败的次数 */
+  attempt: number
+  /** reconnecting 时距离下一次重连的等待时间（毫秒） */
+  delay?: number
+  /** 触发重连或者放弃重连的原因 */
+  error?: string
+}
+export interface SurveyOptions {
+  /** 本次调查的截止时间（毫秒），默认使用 SocketOptions.surveyTime */
+  deadlineMs?: number
+}
+export interface ReceivedMessage {
+  payload: Buffer
+  /** 消息来自的连接（pipe）id，与 pipeAdded / pipeRemoved 事件中的 id 一致 */
+  pipeId?: number
+  localAddress?: string
+  remoteAddress?: string
+  /** 传输方式，取自连接地址的协议部分，例如 tcp、ipc、inproc、ws */
+  transport?: string
+  /** IPC 连接对端进程的用户 id、用户组 id 和进程 id，其他传输方式为空 */
+  peerUid?: number
+  peerGid?: number
+  peerPid?: number
+}
+export interface PipeEventInfo {
+  /** nng 分配的连接 id，同一个连接的 pipeAdded / pipeRemoved 事件 id 相同 */
+  id: number
+  /** 对端地址，例如 tcp://127.0.0.1:50000 */
+  remoteAddress?: string
+  /** IPC 连接对端进程的用户 id，其他传输方式为空 */
+  peerUid?: number
+  /** IPC 连接对端进程的用户组 id */
+  peerGid?: number
+  /** IPC 连接对端的进程 id */
+  peerPid?: number
+  /** WebSocket 连接握手请求的路径（包括查询参数），其他传输方式为空 */
+  requestUri?: string
+  /** WebSocket 连接握手请求的 HTTP 头，listen 端可以据此校验 Authorization、Cookie 等 */
+  requestHeaders?: Record<string, string>
+}
+export const enum Lz4Mode {
+  Default = 'default',
+  Fast = 'fast',
+  HighCompression = 'highCompression'
+}
+export interface Lz4CompressOptions {
+  /** 压缩模式，默认 default */
+  mode?: Lz4Mode
+  /** fast 模式的加速系数，越大越快、压缩率越低，默认 1 */
+  acceleration?: number
+  /** highCompression 模式的压缩级别（1 ~ 12），默认 9 */
+  level?: number
+}
+export const enum CompressionAlgorithm {
+  None = 'none',
+  Lz4 = 'lz4',
+  Zstd = 'zstd'
+}
+export interface CompressionOptions {
+  /** 发送时使用的压缩算法，默认 lz4；none 表示只发送不压缩的消息，但仍能解压对端压缩过的消息 */
+  algorithm?: CompressionAlgorithm
+  /** 小于该字节数的消息不压缩，默认 256 */
+  minSize?: number
+  /** zstd 的压缩级别，默认 3，对 lz4 无效 */
+  level?: number
 }
-export declare function lz4Compress(input: Buffer): Buffer
+export interface IpcOptions {
+  /** listen 创建的 socket 文件权限，例如 0o600，不受 umask 影响，仅 Unix 有效 */
+  permissions?: number
+  /** listen 前删除没有进程在监听的残留 socket 文件（上次进程异常退出留下的），默认 true */
+  removeStale?: boolean
+  /** 只接受这些用户（uid）建立的 IPC 连接，其他连接在建立前就被关闭 */
+  allowedUids?: Array<number>
+  /** 只接受这些用户组（gid）建立的 IPC 连接，与 allowedUids 同时设置时满足其一即可 */
+  allowedGids?: Array<number>
+}
+export const enum TlsAuthMode {
+  None = 'none',
+  Optional = 'optional',
+  Required = 'required'
+}
+export interface TlsOptions {
+  /** 用于校验对端证书的 CA 证书（PEM），可以拼接多个证书。nng 不使用系统的受信任证书，connect 校验服务端证书时必须设置 */
+  ca?: string
+  /** 本端证书或证书链（PEM），listen 时必须设置，connect 时只在服务端要求客户端证书时需要 */
+  cert?: string
+  /** 本端证书的私钥（PEM） */
+  key?: string
+  /** 私钥的密码，私钥没有加密时不需要 */
+  passphrase?: string
+  /** connect 时与服务端证书匹配的名称，默认取连接地址中的主机名 */
+  serverName?: string
+  /** 对端证书的校验方式，connect 默认 required，listen 默认 none */
+  authMode?: TlsAuthMode
+}
+export const enum WebSocketMessageType {
+  Binary = 'binary',
+  Text = 'text'
+}
+export interface WebSocketOptions {
+  /** connect 时随握手请求发送的 HTTP 头，例如 Authorization */
+  requestHeaders?: Record<string, string>
+  /** listen 时随握手响应返回的 HTTP 头 */
+  responseHeaders?: Record<string, string>
+  /** 替换地址中的路径，例如 /nng；同一个端口上的多个 listener 按路径区分 */
+  path?: string
+  /** 发送和接收使用的帧类型，默认 binary */
+  messageType?: WebSocketMessageType
+}
+export declare function lz4Compress(input: Buffer, options?: Lz4CompressOptions | undefined | null): Buffer
+export declare function lz4CompressAsync(input: Buffer, options?: Lz4CompressOptions | undefined | null): Promise<Buffer>
+export declare function lz4CompressBound(len: number): number
+export declare function lz4Decompress(input: Buffer, maxSize?: number | undefined | null): Buffer
+export declare function lz4CompressBlock(input: Buffer, options?: Lz4CompressOptions | undefined | null): Buffer
+export declare function lz4CompressBlockAsync(input: Buffer, options?: Lz4CompressOptions | undefined | null): Promise<Buffer>
+export declare function lz4DecompressBlock(input: Buffer): Buffer
+export declare function lz4FrameCompress(input: Buffer): Buffer
+export declare function lz4FrameDecompress(input: Buffer): Buffer
+export declare function zstdCompress(input: Buffer, level?: number | undefined | null): Buffer
+export declare function zstdCompressAsync(input: Buffer, level?: number | undefined | null): Promise<Buffer>
+export declare function zstdDecompress(input: Buffer, maxSize?: number | undefined | null): Buffer
+export declare function zstdTrainDictionary(samples: Array<Buffer>, maxSize?: number | undefined | null): Buffer
+export declare function zstdCompressWithDictionary(input: Buffer, dictionary: Buffer, level?: number | undefined | null): Buffer
+export declare function zstdDecompressWithDictionary(input: Buffer, dictionary: Buffer, maxSize?: number | undefined | null): Buffer
 export class Socket {
   options: SocketOptions
   constructor(options?: SocketOptions | undefined | null)
   connect(url: string): void
+  listen(url: string): void
   send(req: Buffer): Buffer
+  sendOnly(msg: Buffer): void
+  sendTo(pipeId: number, msg: Buffer): void
+  trySend(msg: Buffer): boolean
+  recv(): Buffer
+  recvAsync(): Promise<Buffer>
+  tryRecv(): Buffer | null
+  sendAsync(req: Buffer): Promise<Buffer>
+  survey(req: Buffer, options?: SurveyOptions | undefined | null): Promise<Array<Buffer>>
+  publish(msg: Buffer): void
+  subscribe(topic: Buffer | string): void
+  unsubscribe(topic: Buffer | string): void
   close(): void
   connected(): boolean
+  _watchPipes(callback: (event: 'pipeAdded' | 'pipeRemoved', pipe: PipeEventInfo) => void): void
+  startReceiving(callback: (err: null | Error, bytes: Buffer, message?: ReceivedMessage) => void, onClose: () => void): void
+  stopReceiving(): void
+  isReceiving(): boolean
   static testConnection(url: string, options?: SocketOptions | undefined | null): boolean
-  static recvMessage(url: string, callback: (err: null | Error, bytes: Buffer) => void, options?: SocketOptions | undefined | null): MessageRecvDisposable
+  static recvMessage(url: string, callback: (err: null | Error, bytes: Buffer, message?: ReceivedMessage) => void, options?: SocketOptions | undefined | null, onStatus?: (status: ReconnectStatus) => void): MessageRecvDisposable
+  static messages(url: string, options?: SocketOptions | undefined | null): MessageStream
+  onMessage(callback: (err: null | Error, bytes: Buffer, message?: ReceivedMessage) => void): MessageRecvDisposable
+  serve(handler: (err: null | Error, req: Buffer) => Promise<Buffer>, concurrency?: number): MessageRecvDisposable
 }
 export class MessageRecvDisposable {
   dispose(): void
   isClosed(): boolean
   isConnectionAlive(): boolean
+  pause(): void
+  resume(): void
+  droppedCount(): number
+  subscribe(topic: Buffer | string): void
+  unsubscribe(topic: Buffer | string): void
+}
+export class MessageStream {
+  recv(): Promise<Buffer | null>
+  dispose(): void
+  isClosed(): boolean
+  isConnectionAlive(): boolean
+  subscribe(topic: Buffer | string): void
+  unsubscribe(topic: Buffer | string): void
+}
+export class Lz4FrameEncoder {
+  constructor()
+  write(chunk: Buffer): Buffer
+  finish(): Buffer
+}
+export class Lz4FrameDecoder {
+  constructor()
+  write(chunk: Buffer): Buffer
+  finish(): Buffer
+}
+export class ZstdDictionary {
+  constructor(dictionary: Buffer, level?: number | undefined | null)
+  compress(input: Buffer): Buffer
+  decompress(input: Buffer, maxSize?: number | undefined | null): Buffer
 }
@@ -281,8 +281,34 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { Socket, MessageRecvDisposable, lz4Compress } = nativeBinding
+const { SocketProtocol, QueuePolicy, ReconnectState, Lz4Mode, CompressionAlgorithm, TlsAuthMode, WebSocketMessageType, Socket, MessageRecvDisposable, MessageStream, Lz4FrameEncoder, Lz4FrameDecoder, ZstdDictionary, lz4Compress, lz4CompressAsync, lz4CompressBound, lz4Decompress, lz4CompressBlock, lz4CompressBlockAsync, lz4DecompressBlock, lz4FrameCompress, lz4FrameDecompress, zstdCompress, zstdCompressAsync, zstdDecompress, zstdTrainDictionary, zstdCompressWithDictionary, zstdDecompressWithDictionary } = nativeBinding
+
+module.exports.SocketProtocol = SocketProtocol
+module.exports.QueuePolicy = QueuePolicy
+module.exports.ReconnectState = ReconnectState
+module.exports.Lz4Mode = Lz4Mode
+module.exports.CompressionAlgorithm = CompressionAlgorithm
+module.exports.TlsAuthMode = TlsAuthMode
+module.exports.WebSocketMessageType = WebSocketMessageType
 
 module.exports.Socket = Socket
 module.exports.MessageRecvDisposable = MessageRecvDisposable
+module.exports.MessageStream = MessageStream
+module.exports.Lz4FrameEncoder = Lz4FrameEncoder
+module.exports.Lz4FrameDecoder = Lz4FrameDecoder
+module.exports.ZstdDictionary = ZstdDictionary
 module.exports.lz4Compress = lz4Compress
+module.exports.lz4CompressAsync = lz4CompressAsync
+module.exports.lz4CompressBound = lz4CompressBound
+module.exports.lz4Decompress = lz4Decompress
+module.exports.lz4CompressBlock = lz4CompressBlock
+module.exports.lz4CompressBlockAsync = lz4CompressBlockAsync
+module.exports.lz4DecompressBlock = lz4DecompressBlock
+module.exports.lz4FrameCompress = lz4FrameCompress
+module.exports.lz4FrameDecompress = lz4FrameDecompress
+module.exports.zstdCompress = zstdCompress
+module.exports.zstdCompressAsync = zstdCompressAsync
+module.exports.zstdDecompress = zstdDecompress
+module.exports.zstdTrainDictionary = zstdTrainDictionary
+module.exports.zstdCompressWithDictionary = zstdCompressWithDictionary
+module.exports.zstdDecompressWithDictionary = zstdDecompressWithDictionary
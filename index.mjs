@@ -288,6 +288,6 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`);
 }
 
-const { Socket, MessageRecvDisposable, lz4Compress } = nativeBinding;
+const { SocketProtocol, QueuePolicy, ReconnectState, Lz4Mode, CompressionAlgorithm, TlsAuthMode, WebSocketMessageType, Socket, MessageRecvDisposable, MessageStream, Lz4FrameEncoder, Lz4FrameDecoder, ZstdDictionary, lz4Compress, lz4CompressAsync, lz4CompressBound, lz4Decompress, lz4CompressBlock, lz4CompressBlockAsync, lz4DecompressBlock, lz4FrameCompress, lz4FrameDecompress, zstdCompress, zstdCompressAsync, zstdDecompress, zstdTrainDictionary, zstdCompressWithDictionary, zstdDecompressWithDictionary } = nativeBinding;
 
-export { Socket, MessageRecvDisposable, lz4Compress };
+export { SocketProtocol, QueuePolicy, ReconnectState, Lz4Mode, CompressionAlgorithm, TlsAuthMode, WebSocketMessageType, Socket, MessageRecvDisposable, MessageStream, Lz4FrameEncoder, Lz4FrameDecoder, ZstdDictionary, lz4Compress, lz4CompressAsync, lz4CompressBound, lz4Decompress, lz4CompressBlock, lz4CompressBlockAsync, lz4DecompressBlock, lz4FrameCompress, lz4FrameDecompress, zstdCompress, zstdCompressAsync, zstdDecompress, zstdTrainDictionary, zstdCompressWithDictionary, zstdDecompressWithDictionary };
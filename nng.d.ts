@@ -0,0 +1,44 @@
+// 包的类型入口：index.d.ts 由 napi build 生成，这里声明 nng.js 在原生接口之上补充或改变的部分
+import { EventEmitter } from 'events'
+
+import {
+  MessageRecvDisposable,
+  MessageStream as NativeMessageStream,
+  PipeEventInfo,
+  ReceivedMessage,
+  Socket as NativeSocket,
+  SocketOptions,
+} from './index'
+
+export * from './index'
+
+/** Socket 支持的事件，其他事件名会抛出 Unknown event */
+export interface SocketEvents {
+  pipeAdded: (pipe: PipeEventInfo) => void
+  pipeRemoved: (pipe: PipeEventInfo) => void
+  message: (bytes: Buffer, message?: ReceivedMessage) => void
+  error: (err: Error) => void
+  close: () => void
+}
+
+/** Socket 的原型链上加入了 EventEmitter */
+export interface Socket extends EventEmitter {}
+
+export declare class Socket extends NativeSocket {
+  on<E extends keyof SocketEvents>(event: E, listener: SocketEvents[E]): this
+  addListener<E extends keyof SocketEvents>(event: E, listener: SocketEvents[E]): this
+  prependListener<E extends keyof SocketEvents>(event: E, listener: SocketEvents[E]): this
+  once<E extends keyof SocketEvents>(event: E, listener: SocketEvents[E]): this
+  off<E extends keyof SocketEvents>(event: E, listener: SocketEvents[E]): this
+  removeListener<E extends keyof SocketEvents>(event: E, listener: SocketEvents[E]): this
+  /** 消息、错误和结束通知通过 on('message' | 'error' | 'close') 分发 */
+  startReceiving(): void
+  /** handler 可以同步返回或同步抛出，也可以返回 Promise */
+  serve(handler: (err: null | Error, req: Buffer) => Buffer | Promise<Buffer>, concurrency?: number): MessageRecvDisposable
+  static messages(url: string, options?: SocketOptions | undefined | null): MessageStream
+}
+
+export declare class MessageStream extends NativeMessageStream {
+  /** for await 读取消息，退出循环（break / return / 抛出异常）时自动 dispose */
+  [Symbol.asyncIterator](): AsyncIterableIterator<Buffer>
+}
@@ -0,0 +1,80 @@
+// 包的入口：index.js / index.d.ts 由 napi build 根据 #[napi] 生成，每次构建都会被覆盖，
+// 原生接口之外需要用 JS 实现的部分放在这里
+const { EventEmitter } = require('events')
+
+const binding = require('./index')
+
+const { Socket, MessageStream } = binding
+
+// serve 的 handler 同步返回或同步抛出时统一转换成 Promise，原生代码只处理 Promise 形式的回复。
+// dispose 之前已经排队等待 JS 线程的请求，在 dispose 之后不再交给 handler
+const nativeServe = Socket.prototype.serve
+Socket.prototype.serve = function (handler, concurrency) {
+  const disposable = nativeServe.call(
+    this,
+    (err, req) =>
+      disposable.isClosed()
+        ? Promise.reject(new Error('Server disposed'))
+        : Promise.resolve().then(() => handler(err, req)),
+    concurrency,
+  )
+  return disposable
+}
+
+// Socket 继承 EventEmitter：pipeAdded / pipeRemoved 来自原生的 _watchPipes，
+// message / error / close 来自原生的 startReceiving
+Object.setPrototypeOf(Socket.prototype, EventEmitter.prototype)
+
+const SOCKET_EVENTS = ['pipeAdded', 'pipeRemoved', 'message', 'error', 'close']
+const watchingPipes = new WeakSet()
+// once / prependOnceListener 最终也会调用这两个方法
+const socketListener = (add) =>
+  function (event, listener) {
+    if (!SOCKET_EVENTS.includes(event)) {
+      throw new Error(`Unknown event ${String(event)} (supported: ${SOCKET_EVENTS.join(', ')})`)
+    }
+    if ((event === 'pipeAdded' || event === 'pipeRemoved') && !watchingPipes.has(this)) {
+      watchingPipes.add(this)
+      this._watchPipes((name, pipe) => this.emit(name, pipe))
+    }
+    return add.call(this, event, listener)
+  }
+Socket.prototype.on = Socket.prototype.addListener = socketListener(EventEmitter.prototype.on)
+Socket.prototype.prependListener = socketListener(EventEmitter.prototype.prependListener)
+
+// 没有 error 监听器时忽略接收错误，EventEmitter 默认会抛出
+const nativeStartReceiving = Socket.prototype.startReceiving
+Socket.prototype.startReceiving = function () {
+  return nativeStartReceiving.call(
+    this,
+    (err, data, message) => {
+      if (!err) this.emit('message', data, message)
+      else if (this.listenerCount('error') > 0) this.emit('error', err)
+    },
+    () => this.emit('close'),
+  )
+}
+
+// for await 读取 MessageStream，退出循环（break / return / 抛出异常）时自动 dispose
+MessageStream.prototype[Symbol.asyncIterator] = function () {
+  const done = () => ({ done: true, value: undefined })
+  return {
+    next: () =>
+      this.recv().then(
+        (value) => (value === null ? done() : { done: false, value }),
+        (err) => {
+          this.dispose()
+          throw err
+        }
+      ),
+    return: () => {
+      this.dispose()
+      return Promise.resolve(done())
+    },
+    [Symbol.asyncIterator]() {
+      return this
+    },
+  }
+}
+
+module.exports = binding
@@ -0,0 +1,7 @@
+// nng.js 的 ES Module 入口，与 require 共用同一份原生模块和 JS 扩展
+import { createRequire } from "module";
+const require = createRequire(import.meta.url);
+
+const { SocketProtocol, QueuePolicy, ReconnectState, Lz4Mode, CompressionAlgorithm, TlsAuthMode, WebSocketMessageType, Socket, MessageRecvDisposable, MessageStream, Lz4FrameEncoder, Lz4FrameDecoder, ZstdDictionary, lz4Compress, lz4CompressAsync, lz4CompressBound, lz4Decompress, lz4CompressBlock, lz4CompressBlockAsync, lz4DecompressBlock, lz4FrameCompress, lz4FrameDecompress, zstdCompress, zstdCompressAsync, zstdDecompress, zstdTrainDictionary, zstdCompressWithDictionary, zstdDecompressWithDictionary } = require("./nng.js");
+
+export { SocketProtocol, QueuePolicy, ReconnectState, Lz4Mode, CompressionAlgorithm, TlsAuthMode, WebSocketMessageType, Socket, MessageRecvDisposable, MessageStream, Lz4FrameEncoder, Lz4FrameDecoder, ZstdDictionary, lz4Compress, lz4CompressAsync, lz4CompressBound, lz4Decompress, lz4CompressBlock, lz4CompressBlockAsync, lz4DecompressBlock, lz4FrameCompress, lz4FrameDecompress, zstdCompress, zstdCompressAsync, zstdDecompress, zstdTrainDictionary, zstdCompressWithDictionary, zstdDecompressWithDictionary };
@@ -0,0 +1,633 @@
+use std::{
+  cell::RefCell,
+  io::{self, Read, Write},
+  ptr,
+  rc::Rc,
+};
+
+use lz4::{
+  block::{compress, compress_bound, decompress, decompress_to_buffer, CompressionMode},
+  liblz4::{
+    check_error, LZ4FDecompressionContext, LZ4F_createDecompressionContext, LZ4F_decompress,
+    LZ4F_freeDecompressionContext, LZ4F_VERSION,
+  },
+  Encoder, EncoderBuilder,
+};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use zstd::{
+  bulk::{Compressor, Decompressor},
+  zstd_safe::get_frame_content_size,
+  Decoder,
+};
+
+// LZ4 的最大压缩比约为 255:1，不知道原始长度时按这个上限逐步扩大输出缓冲区
+const LZ4_MAX_RATIO: usize = 255;
+
+// 流式解码每次调用 LZ4F_decompress 使用的输出缓冲区大小
+const FRAME_CHUNK_SIZE: usize = 64 * 1024;
+
+// zstd 的一个块最多 128KB，最短的 RLE 块也要 4 字节，压缩比不会超过 32768:1，
+// 不知道原始长度时按这个上限限制解压后的大小
+const ZSTD_MAX_RATIO: usize = 32 * 1024;
+
+// 训练 zstd 字典的默认大小，与 zstd 命令行工具一致
+const ZSTD_DICTIONARY_SIZE: u32 = 112640;
+
+fn compress_error(e: io::Error) -> Error {
+  Error::new(Status::GenericFailure, format!("Compression failed: {}", e))
+}
+
+#[napi(string_enum = "camelCase")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lz4Mode {
+  Default,
+  Fast,
+  HighCompression,
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+pub struct Lz4CompressOptions {
+  /// 压缩模式，默认 default
+  pub mode: Option<Lz4Mode>,
+  /// fast 模式的加速系数，越大越快、压缩率越低，默认 1
+  pub acceleration: Option<i32>,
+  /// highCompression 模式的压缩级别（1 ~ 12），默认 9
+  pub level: Option<i32>,
+}
+
+impl Lz4CompressOptions {
+  fn mode(&self) -> CompressionMode {
+    match self.mode.unwrap_or(Lz4Mode::Default) {
+      Lz4Mode::Default => CompressionMode::DEFAULT,
+      Lz4Mode::Fast => CompressionMode::FAST(self.acceleration.unwrap_or(1).max(1)),
+      Lz4Mode::HighCompression => {
+        CompressionMode::HIGHCOMPRESSION(self.level.unwrap_or(9).clamp(1, 12))
+      }
+    }
+  }
+}
+
+fn compress_block(
+  input: &[u8],
+  options: Option<&Lz4CompressOptions>,
+  prepend_size: bool,
+) -> Result<Vec<u8>> {
+  let mode = options.map_or(CompressionMode::DEFAULT, Lz4CompressOptions::mode);
+  compress(input, Some(mode), prepend_size).map_err(compress_error)
+}
+
+fn decompress_error(e: io::Error) -> Error {
+  Error::new(
+    Status::GenericFailure,
+    format!("Decompression failed: {}", e),
+  )
+}
+
+// 不带长度前缀的 LZ4 block，解压时需要通过 lz4Decompress 的 maxSize 指定（或自动探测）输出大小
+#[napi]
+pub fn lz4_compress(input: Buffer, options: Option<Lz4CompressOptions>) -> Result<Buffer> {
+  compress_block(&input, options.as_ref(), false).map(Buffer::from)
+}
+
+// 在 libuv 线程池中压缩，适合较大的数据，不阻塞事件循环
+#[napi]
+pub fn lz4_compress_async(
+  input: Buffer,
+  options: Option<Lz4CompressOptions>,
+) -> AsyncTask<Lz4CompressTask> {
+  AsyncTask::new(Lz4CompressTask {
+    input,
+    options,
+    prepend_size: false,
+  })
+}
+
+// 压缩 len 字节的数据最多需要的输出大小，可用于预先分配缓冲区
+#[napi]
+pub fn lz4_compress_bound(len: u32) -> Result<u32> {
+  let bound = compress_bound(len as usize).map_err(compress_error)?;
+  u32::try_from(bound).map_err(|_| Error::new(Status::InvalidArg, "len is too large".to_string()))
+}
+
+// 解压 lz4Compress 的输出，maxSize 为解压后大小的上限，不传时按最大压缩比逐步尝试
+#[napi]
+pub fn lz4_decompress(input: Buffer, max_size: Option<u32>) -> Result<Buffer> {
+  if let Some(max_size) = max_size {
+    let max_size = i32::try_from(max_size)
+      .map_err(|_| Error::new(Status::InvalidArg, "maxSize is too large".to_string()))?;
+    return decompress(&input, Some(max_size))
+      .map(Buffer::from)
+      .map_err(decompress_error);
+  }
+
+  let limit = input
+    .len()
+    .saturating_mul(LZ4_MAX_RATIO)
+    .clamp(FRAME_CHUNK_SIZE, i32::MAX as usize);
+  let mut capacity = input.len().saturating_mul(4).clamp(1024, limit);
+  loop {
+    let mut buffer = vec![0u8; capacity];
+    match decompress_to_buffer(&input, Some(capacity as i32), &mut buffer) {
+      Ok(size) => {
+        buffer.truncate(size);
+        return Ok(buffer.into());
+      }
+      // 输出缓冲区不够和数据损坏返回的是同一个错误，到上限后才判定为损坏
+      Err(e) if capacity >= limit => return Err(decompress_error(e)),
+      Err(_) => capacity = capacity.saturating_mul(4).min(limit),
+    }
+  }
+}
+
+// 带 4 字节小端长度前缀的 LZ4 block，与 python-lz4 的 store_size=True 格式相同
+#[napi]
+pub fn lz4_compress_block(input: Buffer, options: Option<Lz4CompressOptions>) -> Result<Buffer> {
+  compress_block(&input, options.as_ref(), true).map(Buffer::from)
+}
+
+#[napi]
+pub fn lz4_compress_block_async(
+  input: Buffer,
+  options: Option<Lz4CompressOptions>,
+) -> AsyncTask<Lz4CompressTask> {
+  AsyncTask::new(Lz4CompressTask {
+    input,
+    options,
+    prepend_size: true,
+  })
+}
+
+// 长度前缀可能是伪造的，先按最大压缩比检查，避免按它分配内存
+fn check_lz4_size_prefix(input: &[u8]) -> Result<()> {
+  let size = input
+    .get(..4)
+    .map_or(0, |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+  if size > input.len().saturating_mul(LZ4_MAX_RATIO) {
+    return Err(Error::new(
+      Status::GenericFailure,
+      format!("Decompression failed: invalid LZ4 size prefix {}", size),
+    ));
+  }
+  Ok(())
+}
+
+#[napi]
+pub fn lz4_decompress_block(input: Buffer) -> Result<Buffer> {
+  check_lz4_size_prefix(&input)?;
+  decompress(&input, None)
+    .map(Buffer::from)
+    .map_err(decompress_error)
+}
+
+// LZ4 frame 格式，与 lz4 命令行工具和 .lz4 文件兼容
+#[napi]
+pub fn lz4_frame_compress(input: Buffer) -> Result<Buffer> {
+  let mut encoder = EncoderBuilder::new()
+    .build(Vec::new())
+    .map_err(compress_error)?;
+  encoder.write_all(&input).map_err(compress_error)?;
+  let (output, result) = encoder.finish();
+  result.map_err(compress_error)?;
+  Ok(output.into())
+}
+
+#[napi]
+pub fn lz4_frame_decompress(input: Buffer) -> Result<Buffer> {
+  let mut decoder = Lz4FrameDecoder::new()?;
+  let output = decoder.decode(&input)?;
+  decoder.ensure_complete()?;
+  Ok(output.into())
+}
+
+pub struct Lz4CompressTask {
+  input: Buffer,
+  options: Option<Lz4CompressOptions>,
+  prepend_size: bool,
+}
+
+impl Task for Lz4CompressTask {
+  type Output = Vec<u8>;
+  type JsValue = Buffer;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    compress_block(&self.input, self.options.as_ref(), self.prepend_size)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output.into())
+  }
+}
+
+// 流式编码器的输出缓冲区，每次 write 之后取走已经产生的数据
+#[derive(Clone, Default)]
+struct SharedSink(Rc<RefCell<Vec<u8>>>);
+
+impl SharedSink {
+  fn take(&self) -> Vec<u8> {
+    std::mem::take(&mut self.0.borrow_mut())
+  }
+}
+
+impl Write for SharedSink {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.0.borrow_mut().extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+// 流式 LZ4 frame 编码器：适合在 Transform 流的 transform / flush 中分块调用
+#[napi]
+pub struct Lz4FrameEncoder {
+  encoder: Option<Encoder<SharedSink>>,
+  sink: SharedSink,
+}
+
+#[napi]
+impl Lz4FrameEncoder {
+  #[napi(constructor)]
+  pub fn new() -> Result<Self> {
+    let sink = SharedSink::default();
+    let encoder = EncoderBuilder::new()
+      .build(sink.clone())
+      .map_err(compress_error)?;
+    Ok(Lz4FrameEncoder {
+      encoder: Some(encoder),
+      sink,
+    })
+  }
+
+  // 写入一块数据，返回目前已经产生的压缩数据（LZ4 按块压缩，可能为空）
+  #[napi]
+  pub fn write(&mut self, chunk: Buffer) -> Result<Buffer> {
+    let encoder = self
+      .encoder
+      .as_mut()
+      .ok_or_else(|| Error::from_reason("Encoder already finished".to_string()))?;
+    encoder.write_all(&chunk).map_err(compress_error)?;
+    Ok(self.sink.take().into())
+  }
+
+  // 结束 frame，返回剩余的压缩数据，之后不能再 write
+  #[napi]
+  pub fn finish(&mut self) -> Result<Buffer> {
+    let encoder = self
+      .encoder
+      .take()
+      .ok_or_else(|| Error::from_reason("Encoder already finished".to_string()))?;
+    let (_, result) = encoder.finish();
+    result.map_err(compress_error)?;
+    Ok(self.sink.take().into())
+  }
+}
+
+// 流式 LZ4 frame 解码器：输入可以任意切分，支持多个 frame 首尾相连
+#[napi]
+pub struct Lz4FrameDecoder {
+  ctx: LZ4FDecompressionContext,
+  // 当前 frame 是否还没有读完
+  in_frame: bool,
+}
+
+#[napi]
+impl Lz4FrameDecoder {
+  #[napi(constructor)]
+  pub fn new() -> Result<Self> {
+    let mut ctx = LZ4FDecompressionContext(ptr::null_mut());
+    // SAFETY: ctx 只作为输出参数，成功时得到的上下文归 Lz4FrameDecoder 所有并在 Drop 中释放，
+    // 失败时不会构造 Lz4FrameDecoder，也就不会释放
+    check_error(unsafe { LZ4F_createDecompressionContext(&mut ctx, LZ4F_VERSION) })
+      .map_err(decompress_error)?;
+    Ok(Lz4FrameDecoder {
+      ctx,
+      in_frame: false,
+    })
+  }
+
+  // 写入一块压缩数据，返回目前能解出的原始数据
+  #[napi]
+  pub fn write(&mut self, chunk: Buffer) -> Result<Buffer> {
+    self.decode(&chunk).map(Buffer::from)
+  }
+
+  // 检查输入是否停在 frame 边界上，被截断时报错
+  #[napi]
+  pub fn finish(&mut self) -> Result<Buffer> {
+    self.ensure_complete()?;
+    Ok(Vec::new().into())
+  }
+}
+
+impl Lz4FrameDecoder {
+  fn decode(&mut self, mut src: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut buffer = vec![0u8; FRAME_CHUNK_SIZE];
+    loop {
+      let mut src_size = src.len();
+      let mut dst_size = buffer.len();
+      // SAFETY: self.ctx 在 new 中创建，直到 Drop 前都有效，&mut self 保证同一时间只有一个调用；
+      // dst_size 和 src_size 传入 buffer 和 src 的实际长度，LZ4F 只在这个范围内读写，
+      // 返回时改为实际写入和消费的字节数，因此下面的切片不会越界
+      let hint = check_error(unsafe {
+        LZ4F_decompress(
+          self.ctx,
+          buffer.as_mut_ptr(),
+          &mut dst_size,
+          src.as_ptr(),
+          &mut src_size,
+          ptr::null(),
+        )
+      })
+      .map_err(decompress_error)?;
+      output.extend_from_slice(&buffer[..dst_size]);
+      src = &src[src_size..];
+      if src_size > 0 {
+        self.in_frame = true;
+      }
+      // hint 为 0 表示一个 frame 结束，上下文会自动准备好解码下一个 frame
+      if hint == 0 {
+        self.in_frame = false;
+      }
+      if src.is_empty() && dst_size == 0 {
+        return Ok(output);
+      }
+    }
+  }
+
+  fn ensure_complete(&self) -> Result<()> {
+    if self.in_frame {
+      return Err(Error::new(
+        Status::GenericFailure,
+        "Decompression failed: truncated LZ4 frame".to_string(),
+      ));
+    }
+    Ok(())
+  }
+}
+
+impl Drop for Lz4FrameDecoder {
+  fn drop(&mut self) {
+    // SAFETY: self.ctx 由 new 创建且只在这里释放一次，之后不再使用
+    unsafe { LZ4F_freeDecompressionContext(self.ctx) };
+  }
+}
+
+// zstd 的压缩级别，默认 3，超出范围时取最近的有效值（最高 22，负数为更快的快速模式）
+fn zstd_level(level: Option<i32>) -> i32 {
+  let range = zstd::compression_level_range();
+  level
+    .unwrap_or(zstd::DEFAULT_COMPRESSION_LEVEL)
+    .clamp(*range.start(), *range.end())
+}
+
+fn zstd_compress_with(input: &[u8], dictionary: &[u8], level: Option<i32>) -> Result<Vec<u8>> {
+  Compressor::with_dictionary(zstd_level(level), dictionary)
+    .and_then(|mut compressor| compressor.compress(input))
+    .map_err(compress_error)
+}
+
+// maxSize 为解压后大小的上限；不传时上限为输入大小乘以 zstd 的最大压缩比，
+// 按 frame 头中记录的原始大小分配（不超过上限），流式压缩产生的 frame 没有记录原始大小，改用流式解码
+fn zstd_decompress_with(
+  decompressor: &mut Decompressor<'static>,
+  dictionary: &[u8],
+  input: &[u8],
+  max_size: Option<u32>,
+) -> Result<Vec<u8>> {
+  if let Some(max_size) = max_size {
+    return decompressor
+      .decompress(input, max_size as usize)
+      .map_err(decompress_error);
+  }
+  let limit = input.len().saturating_mul(ZSTD_MAX_RATIO);
+  let content_size = get_frame_content_size(input)
+    .ok()
+    .flatten()
+    .and_then(|size| usize::try_from(size).ok());
+  match content_size {
+    // frame 头来自输入，同样可能是伪造的
+    Some(size) => decompressor
+      .decompress(input, size.min(limit))
+      .map_err(decompress_error),
+    None => zstd_decode_bounded(
+      Decoder::with_dictionary(input, dictionary).map_err(decompress_error)?,
+      limit,
+    ),
+  }
+}
+
+// 流式解码，输出超过 limit 时报错，不会一次性分配对端声称的大小
+fn zstd_decode_bounded(decoder: impl Read, limit: usize) -> Result<Vec<u8>> {
+  let mut output = Vec::new();
+  decoder
+    .take(limit as u64 + 1)
+    .read_to_end(&mut output)
+    .map_err(decompress_error)?;
+  if output.len() > limit {
+    return Err(Error::new(
+      Status::GenericFailure,
+      format!("Decompression failed: output exceeds {} bytes", limit),
+    ));
+  }
+  Ok(output)
+}
+
+fn zstd_decompressor(dictionary: &[u8]) -> Result<Decompressor<'static>> {
+  Decompressor::with_dictionary(dictionary).map_err(decompress_error)
+}
+
+// zstd frame 格式，与 zstd 命令行工具和 .zst 文件兼容，level 默认 3
+#[napi]
+pub fn zstd_compress(input: Buffer, level: Option<i32>) -> Result<Buffer> {
+  zstd_compress_with(&input, &[], level).map(Buffer::from)
+}
+
+#[napi]
+pub fn zstd_compress_async(input: Buffer, level: Option<i32>) -> AsyncTask<ZstdCompressTask> {
+  AsyncTask::new(ZstdCompressTask { input, level })
+}
+
+#[napi]
+pub fn zstd_decompress(input: Buffer, max_size: Option<u32>) -> Result<Buffer> {
+  zstd_decompress_with(&mut zstd_decompressor(&[])?, &[], &input, max_size).map(Buffer::from)
+}
+
+// 从一组样本中训练字典，样本应当是与实际消息相似的数据，数量越多效果越好（一般至少几百条）
+#[napi]
+pub fn zstd_train_dictionary(samples: Vec<Buffer>, max_size: Option<u32>) -> Result<Buffer> {
+  zstd::dict::from_samples(&samples, max_size.unwrap_or(ZSTD_DICTIONARY_SIZE) as usize)
+    .map(Buffer::from)
+    .map_err(|e| {
+      Error::new(
+        Status::GenericFailure,
+        format!("Train dictionary failed: {}", e),
+      )
+    })
+}
+
+// 使用共享字典压缩，解压时必须使用同一个字典；反复使用同一个字典时 ZstdDictionary 更高效
+#[napi]
+pub fn zstd_compress_with_dictionary(
+  input: Buffer,
+  dictionary: Buffer,
+  level: Option<i32>,
+) -> Result<Buffer> {
+  zstd_compress_with(&input, &dictionary, level).map(Buffer::from)
+}
+
+#[napi]
+pub fn zstd_decompress_with_dictionary(
+  input: Buffer,
+  dictionary: Buffer,
+  max_size: Option<u32>,
+) -> Result<Buffer> {
+  let mut decompressor = zstd_decompressor(&dictionary)?;
+  zstd_decompress_with(&mut decompressor, &dictionary, &input, max_size).map(Buffer::from)
+}
+
+pub struct ZstdCompressTask {
+  input: Buffer,
+  level: Option<i32>,
+}
+
+impl Task for ZstdCompressTask {
+  type Output = Vec<u8>;
+  type JsValue = Buffer;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    zstd_compress_with(&self.input, &[], self.level)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output.into())
+  }
+}
+
+// 预先加载字典的压缩器 / 解压器，字典只解析一次，适合用同一个字典处理大量小消息
+#[napi]
+pub struct ZstdDictionary {
+  dictionary: Vec<u8>,
+  compressor: Compressor<'static>,
+  decompressor: Decompressor<'static>,
+}
+
+#[napi]
+impl ZstdDictionary {
+  #[napi(constructor)]
+  pub fn new(dictionary: Buffer, level: Option<i32>) -> Result<Self> {
+    Ok(ZstdDictionary {
+      compressor: Compressor::with_dictionary(zstd_level(level), &dictionary)
+        .map_err(compress_error)?,
+      decompressor: zstd_decompressor(&dictionary)?,
+      dictionary: dictionary.to_vec(),
+    })
+  }
+
+  #[napi]
+  pub fn compress(&mut self, input: Buffer) -> Result<Buffer> {
+    self
+      .compressor
+      .compress(&input)
+      .map(Buffer::from)
+      .map_err(compress_error)
+  }
+
+  #[napi]
+  pub fn decompress(&mut self, input: Buffer, max_size: Option<u32>) -> Result<Buffer> {
+    zstd_decompress_with(&mut self.decompressor, &self.dictionary, &input, max_size)
+      .map(Buffer::from)
+  }
+}
+
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+  None,
+  Lz4,
+  Zstd,
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+pub struct CompressionOptions {
+  /// 发送时使用的压缩算法，默认 lz4；none 表示只发送不压缩的消息，但仍能解压对端压缩过的消息
+  pub algorithm: Option<CompressionAlgorithm>,
+  /// 小于该字节数的消息不压缩，默认 256
+  pub min_size: Option<u32>,
+  /// zstd 的压缩级别，默认 3，对 lz4 无效
+  pub level: Option<i32>,
+}
+
+// 压缩过的消息以 MARKER 开头，后面一个字节是压缩算法。0xFE 不会出现在 UTF-8 文本中，
+// 没有 MARKER 的消息按原样交给 JS，因此可以接收未开启 compression 的对端发来的消息
+const MARKER: [u8; 4] = [0xFE, b'N', b'N', b'Z'];
+const HEADER_RAW: u8 = 0;
+const HEADER_LZ4: u8 = 1;
+const HEADER_ZSTD: u8 = 2;
+
+impl CompressionOptions {
+  // 压缩后变小的消息加上 MARKER 和算法发送，其余按原样发送；
+  // 原始内容恰好以 MARKER 开头时标记为未压缩，避免接收方误解压
+  pub(crate) fn encode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+    let algorithm = self.algorithm.unwrap_or(CompressionAlgorithm::Lz4);
+    let compressed = if payload.len() < self.min_size.unwrap_or(256) as usize {
+      None
+    } else {
+      match algorithm {
+        CompressionAlgorithm::None => None,
+        CompressionAlgorithm::Lz4 => Some((HEADER_LZ4, compress_block(payload, None, true)?)),
+        CompressionAlgorithm::Zstd => {
+          Some((HEADER_ZSTD, zstd_compress_with(payload, &[], self.level)?))
+        }
+      }
+    };
+    let (header, body) = match &compressed {
+      Some((header, body)) if body.len() + MARKER.len() + 1 < payload.len() => {
+        (*header, body.as_slice())
+      }
+      _ if payload.starts_with(&MARKER) => (HEADER_RAW, payload),
+      _ => return Ok(payload.to_vec()),
+    };
+    let mut output = Vec::with_capacity(body.len() + MARKER.len() + 1);
+    output.extend_from_slice(&MARKER);
+    output.push(header);
+    output.extend_from_slice(body);
+    Ok(output)
+  }
+}
+
+// 按 MARKER 后的算法还原消息体，不依赖本端的压缩算法设置；没有 MARKER 的消息原样返回
+pub(crate) fn decode_payload(data: &[u8]) -> Result<Vec<u8>> {
+  let Some(marked) = data.strip_prefix(&MARKER[..]) else {
+    return Ok(data.to_vec());
+  };
+  let (header, body) = marked.split_first().ok_or_else(|| {
+    Error::new(
+      Status::GenericFailure,
+      "Decompression failed: missing compression algorithm".to_string(),
+    )
+  })?;
+  match *header {
+    HEADER_RAW => Ok(body.to_vec()),
+    HEADER_LZ4 => {
+      check_lz4_size_prefix(body)?;
+      decompress(body, None).map_err(decompress_error)
+    }
+    HEADER_ZSTD => zstd_decode_bounded(
+      Decoder::new(body).map_err(decompress_error)?,
+      body.len().saturating_mul(ZSTD_MAX_RATIO),
+    ),
+    header => Err(Error::new(
+      Status::GenericFailure,
+      format!(
+        "Decompression failed: unknown compression algorithm {}",
+        header
+      ),
+    )),
+  }
+}
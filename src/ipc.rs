@@ -0,0 +1,112 @@
+use napi_derive::napi;
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+pub struct IpcOptions {
+  /// listen 创建的 socket 文件权限，例如 0o600，不受 umask 影响，仅 Unix 有效
+  pub permissions: Option<u32>,
+  /// listen 前删除没有进程在监听的残留 socket 文件（上次进程异常退出留下的），默认 true
+  pub remove_stale: Option<bool>,
+  /// 只接受这些用户（uid）建立的 IPC 连接，其他连接在建立前就被关闭
+  pub allowed_uids: Option<Vec<u32>>,
+  /// 只接受这些用户组（gid）建立的 IPC 连接，与 allowedUids 同时设置时满足其一即可
+  pub allowed_gids: Option<Vec<u32>>,
+}
+
+impl IpcOptions {
+  // ipc:// 地址的 listener：清理残留的 socket 文件并设置文件权限，由调用方启动
+  pub(crate) fn listener(
+    &self,
+    client: &nng::Socket,
+    url: &str,
+  ) -> nng::Result<nng::ListenerBuilder> {
+    if self.remove_stale.unwrap_or(true) {
+      if let Some(path) = url.strip_prefix("ipc://") {
+        remove_stale_socket(path);
+      }
+    }
+    let builder = nng::ListenerBuilder::new(client, url)?;
+    if let Some(mode) = self.permissions {
+      set_permissions(&builder, mode)?;
+    }
+    Ok(builder)
+  }
+
+  // 在连接建立前（AddPre）调用：只检查 IPC 连接，读不到对端凭据的 IPC 连接按拒绝处理
+  pub(crate) fn accepts(&self, pipe: nng::Pipe, transport: Option<&str>) -> bool {
+    if (self.allowed_uids.is_none() && self.allowed_gids.is_none()) || transport != Some("ipc") {
+      return true;
+    }
+    let peer = PeerCredentials::of(pipe);
+    let allowed = |ids: &Option<Vec<u32>>, id: Option<u32>| match (ids, id) {
+      (Some(ids), Some(id)) => ids.contains(&id),
+      _ => false,
+    };
+    allowed(&self.allowed_uids, peer.uid) || allowed(&self.allowed_gids, peer.gid)
+  }
+}
+
+// IPC 连接对端进程的凭据，由操作系统提供，不能伪造；其他传输方式全部为 None
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PeerCredentials {
+  pub uid: Option<u32>,
+  pub gid: Option<u32>,
+  pub pid: Option<u32>,
+}
+
+impl PeerCredentials {
+  pub(crate) fn of(pipe: nng::Pipe) -> Self {
+    PeerCredentials {
+      uid: pipe_uint64(pipe, nng_sys::NNG_OPT_IPC_PEER_UID),
+      gid: pipe_uint64(pipe, nng_sys::NNG_OPT_IPC_PEER_GID),
+      pid: pipe_uint64(pipe, nng_sys::NNG_OPT_IPC_PEER_PID),
+    }
+  }
+}
+
+// nng crate 把 PeerUid / PeerGid 错误地实现成了 Pipe 的可写选项，无法读取，这里直接调用 nng_sys
+fn pipe_uint64(pipe: nng::Pipe, option: &[u8]) -> Option<u32> {
+  let mut value = 0u64;
+  // SAFETY: option 是 nng_sys 中以 \0 结尾的常量，pipe 已经失效时 nng 只返回错误码
+  let rv =
+    unsafe { nng_sys::nng_pipe_get_uint64(pipe.nng_pipe(), option.as_ptr() as _, &mut value) };
+  if rv != 0 {
+    return None;
+  }
+  u32::try_from(value).ok()
+}
+
+#[cfg(unix)]
+fn set_permissions(builder: &nng::ListenerBuilder, mode: u32) -> nng::Result<()> {
+  use nng::options::{transport::ipc::Permissions, Options};
+  builder.set_opt::<Permissions>(mode)
+}
+
+#[cfg(not(unix))]
+fn set_permissions(_: &nng::ListenerBuilder, _: u32) -> nng::Result<()> {
+  Err(nng::Error::NotSupported)
+}
+
+// 连接被拒绝说明没有进程在监听，socket 文件是残留的；普通文件和正在使用的 socket 不会被删除
+#[cfg(unix)]
+fn remove_stale_socket(path: &str) {
+  use std::{
+    io,
+    os::unix::{fs::FileTypeExt, net::UnixStream},
+  };
+  let Ok(metadata) = std::fs::symlink_metadata(path) else {
+    return;
+  };
+  if !metadata.file_type().is_socket() {
+    return;
+  }
+  if let Err(e) = UnixStream::connect(path) {
+    if e.kind() == io::ErrorKind::ConnectionRefused {
+      let _ = std::fs::remove_file(path);
+    }
+  }
+}
+
+// Windows 上 IPC 使用命名管道，不会留下文件
+#[cfg(not(unix))]
+fn remove_stale_socket(_: &str) {}
@@ -1,5 +1,9 @@
 #![deny(clippy::all)]
 
+mod compression;
+mod ipc;
 mod nanomsg;
+mod tls;
+mod websocket;
 
 extern crate napi_derive;
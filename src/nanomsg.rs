@@ -1,37 +1,672 @@
-use lz4::block::compress;
-use lz4::block::CompressionMode;
 use napi::{
   bindgen_prelude::*,
-  threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode},
+  threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+  },
+  JsDeferred, JsFunction, JsObject, NapiRaw,
 };
 use napi_derive::napi;
 use std::{
+  collections::{hash_map::RandomState, HashMap, VecDeque},
+  hash::{BuildHasher, Hasher},
   sync::{
-    atomic::{AtomicBool, Ordering},
-    mpsc::{self, Sender},
-    Arc,
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    mpsc::{self, RecvTimeoutError, Sender},
+    Arc, Condvar, Mutex, OnceLock,
   },
   thread,
-  time::Duration,
+  time::{Duration, Instant},
 };
 
 use nng::{
-  options::{Options, RecvTimeout, SendTimeout},
-  Protocol,
+  options::{
+    protocol::{
+      pubsub::{Subscribe, Unsubscribe},
+      reqrep::ResendTime,
+      survey::SurveyTime,
+    },
+    LocalAddr, Options, ReconnectMaxTime, ReconnectMinTime, RecvTimeout, RemAddr, SendTimeout, Url,
+  },
+  Aio, AioResult, Context, Protocol,
+};
+
+use crate::{
+  compression::{decode_payload, CompressionOptions},
+  ipc::{IpcOptions, PeerCredentials},
+  tls::{self, TlsOptions},
+  websocket::{self, WebSocketOptions, WebSocketRequest},
 };
 
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum SocketProtocol {
+  Pair0,
+  Pair1,
+  Req,
+  Rep,
+  Pub,
+  Sub,
+  Push,
+  Pull,
+  Surveyor,
+  Respondent,
+  Bus,
+}
+
+impl From<SocketProtocol> for Protocol {
+  fn from(protocol: SocketProtocol) -> Self {
+    match protocol {
+      SocketProtocol::Pair0 => Protocol::Pair0,
+      SocketProtocol::Pair1 => Protocol::Pair1,
+      SocketProtocol::Req => Protocol::Req0,
+      SocketProtocol::Rep => Protocol::Rep0,
+      SocketProtocol::Pub => Protocol::Pub0,
+      SocketProtocol::Sub => Protocol::Sub0,
+      SocketProtocol::Push => Protocol::Push0,
+      SocketProtocol::Pull => Protocol::Pull0,
+      SocketProtocol::Surveyor => Protocol::Surveyor0,
+      SocketProtocol::Respondent => Protocol::Respondent0,
+      SocketProtocol::Bus => Protocol::Bus0,
+    }
+  }
+}
+
+impl SocketProtocol {
+  fn name(self) -> &'static str {
+    match self {
+      SocketProtocol::Pair0 => "pair0",
+      SocketProtocol::Pair1 => "pair1",
+      SocketProtocol::Req => "req",
+      SocketProtocol::Rep => "rep",
+      SocketProtocol::Pub => "pub",
+      SocketProtocol::Sub => "sub",
+      SocketProtocol::Push => "push",
+      SocketProtocol::Pull => "pull",
+      SocketProtocol::Surveyor => "surveyor",
+      SocketProtocol::Respondent => "respondent",
+      SocketProtocol::Bus => "bus",
+    }
+  }
+
+  // 校验当前协议是否支持 method，不支持时在错误中列出可用的协议
+  fn ensure(self, method: &str, allowed: &[SocketProtocol]) -> Result<()> {
+    if allowed.contains(&self) {
+      return Ok(());
+    }
+    let supported = allowed
+      .iter()
+      .map(|p| p.name())
+      .collect::<Vec<_>>()
+      .join(", ");
+    Err(Error::new(
+      Status::InvalidArg,
+      format!(
+        "{} is not supported on a {} socket (supported: {})",
+        method,
+        self.name(),
+        supported
+      ),
+    ))
+  }
+}
+
+// 发送后等待回复的协议
+const REQUEST_PROTOCOLS: &[SocketProtocol] = &[
+  SocketProtocol::Pair0,
+  SocketProtocol::Pair1,
+  SocketProtocol::Req,
+];
+
+// 可以直接发送单向消息的协议
+const SEND_PROTOCOLS: &[SocketProtocol] = &[
+  SocketProtocol::Pair0,
+  SocketProtocol::Pair1,
+  SocketProtocol::Pub,
+  SocketProtocol::Push,
+  SocketProtocol::Bus,
+];
+
+// 可以持续接收消息的协议
+const RECV_PROTOCOLS: &[SocketProtocol] = &[
+  SocketProtocol::Pair0,
+  SocketProtocol::Pair1,
+  SocketProtocol::Sub,
+  SocketProtocol::Pull,
+  SocketProtocol::Bus,
+];
+
+// 可以收发心跳的双向协议
+const HEARTBEAT_PROTOCOLS: &[SocketProtocol] = &[
+  SocketProtocol::Pair0,
+  SocketProtocol::Pair1,
+  SocketProtocol::Bus,
+];
+
+// 可以开启透明压缩的协议：Sub 的订阅按消息开头的字节匹配，加上压缩头之后无法匹配主题
+const COMPRESSION_PROTOCOLS: &[SocketProtocol] = &[
+  SocketProtocol::Pair0,
+  SocketProtocol::Pair1,
+  SocketProtocol::Req,
+  SocketProtocol::Rep,
+  SocketProtocol::Push,
+  SocketProtocol::Pull,
+  SocketProtocol::Surveyor,
+  SocketProtocol::Respondent,
+  SocketProtocol::Bus,
+];
+
+// 通过 handler 回复请求的协议
+const SERVE_PROTOCOLS: &[SocketProtocol] = &[SocketProtocol::Rep, SocketProtocol::Respondent];
+
+// serve 默认的并发处理数（每个并发占用一个线程和一个 Context）
+const SERVE_CONCURRENCY: u32 = 4;
+
+// messages() 的队列中默认最多缓存的消息数，超过后接收线程等待 JS 取走消息
+const MESSAGE_STREAM_CAPACITY: usize = 16;
+
+#[napi(string_enum = "camelCase")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum QueuePolicy {
+  // 暂停从 nng 接收，直到回调处理掉积压的消息
+  Block,
+  // 丢弃最早积压的消息
+  DropOldest,
+  // 丢弃新收到的消息
+  DropNewest,
+  // 通过回调报错并停止接收
+  Error,
+}
+
 #[napi(object)]
 #[derive(Clone, Debug, Default)]
 pub struct SocketOptions {
   pub recv_timeout: Option<i32>,
   pub send_timeout: Option<i32>,
+  /// 协议类型，默认 pair1
+  pub protocol: Option<SocketProtocol>,
+  /// Req 协议下未收到回复时自动重发请求的间隔（毫秒）
+  pub resend_interval: Option<i32>,
+  /// Surveyor 协议下默认的调查截止时间（毫秒）
+  pub survey_time: Option<i32>,
+  /// 是否启用心跳（recvMessage / onMessage 接收期间定时发送 ping 并检测对端是否存活）
+  pub enable_heartbeat: Option<bool>,
+  /// 心跳间隔（毫秒），默认 3000
+  pub heartbeat_interval: Option<i32>,
+  /// 连续多少个心跳间隔没有收到任何消息时判定连接断开，默认 3
+  pub heartbeat_max_missed: Option<u32>,
+  /// ping 消息内容，默认 "__nng_ping__"
+  pub heartbeat_payload: Option<String>,
+  /// 自动回复的 pong 消息内容，默认 "__nng_pong__"
+  pub heartbeat_reply_payload: Option<String>,
+  /// 收到对端的 ping 时是否自动回复 pong（不需要启用心跳也可以单独开启）
+  pub heartbeat_auto_reply: Option<bool>,
+  /// recvMessage 的自动重连策略，不设置时连接出错后停止接收
+  pub reconnect: Option<ReconnectOptions>,
+  /// nng 底层连接断开后重新连接的最短间隔（毫秒），nng 默认 100
+  pub reconnect_min_time: Option<i32>,
+  /// nng 底层重连间隔指数增长的上限（毫秒），nng 默认 0（不增长，始终使用 reconnectMinTime）
+  pub reconnect_max_time: Option<i32>,
+  /// connect / recvMessage 时不等待连接建立，对端未启动时在后台按重连间隔继续尝试
+  pub non_blocking_dial: Option<bool>,
+  /// recvMessage / onMessage 的回调额外收到带连接信息的 ReceivedMessage
+  pub message_metadata: Option<bool>,
+  /// 透明压缩：发送时自动压缩、接收时自动解压，通信双方都需要开启
+  pub compression: Option<CompressionOptions>,
+  /// recvMessage / onMessage 最多积压多少条还没交给回调的消息，默认不限制；messages() 的缓存条数，默认 16
+  pub max_queue_size: Option<u32>,
+  /// 积压达到 maxQueueSize 时的处理方式，默认 block
+  pub queue_policy: Option<QueuePolicy>,
+  /// ipc:// 地址的 socket 文件权限、残留文件清理以及按对端用户过滤连接
+  pub ipc: Option<IpcOptions>,
+  /// tls+tcp:// 和 wss:// 地址的证书配置，需要以 tls feature 编译
+  pub tls: Option<TlsOptions>,
+  /// ws:// 和 wss:// 地址的握手 HTTP 头、路径和帧类型
+  pub web_socket: Option<WebSocketOptions>,
+  /// Pair1 协议下允许同时连接多个对端，配合 sendTo 回复指定的对端
+  pub polyamorous: Option<bool>,
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+pub struct ReconnectOptions {
+  /// 第一次重连前的等待时间（毫秒），默认 100
+  pub initial_delay: Option<i32>,
+  /// 指数退避的最长等待时间（毫秒），默认 30000
+  pub max_delay: Option<i32>,
+  /// 等待时间的随机浮动比例（0 ~ 1），默认 0.2
+  pub jitter: Option<f64>,
+  /// 最多连续重连的次数，不设置时一直重连
+  pub max_attempts: Option<u32>,
+}
+
+impl ReconnectOptions {
+  // 第 attempt 次重连前的等待时间：指数退避，并在 ±jitter 范围内随机浮动，避免多个客户端同时重连
+  fn delay(&self, attempt: u32) -> Duration {
+    let initial = self
+      .initial_delay
+      .and_then(|i| u64::try_from(i).ok())
+      .unwrap_or(100);
+    let max = self
+      .max_delay
+      .and_then(|i| u64::try_from(i).ok())
+      .unwrap_or(30000)
+      .max(initial);
+    let base = initial
+      .saturating_mul(1u64 << attempt.saturating_sub(1).min(32))
+      .min(max);
+    let jitter = self.jitter.unwrap_or(0.2).clamp(0.0, 1.0);
+    let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+    Duration::from_millis((base as f64 * (1.0 + jitter * (random * 2.0 - 1.0))) as u64)
+  }
+}
+
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReconnectState {
+  Connecting,
+  Connected,
+  Reconnecting,
+  #[napi(value = "gave-up")]
+  GaveUp,
+}
+
+#[napi(object)]
+pub struct ReconnectStatus {
+  pub state: ReconnectState,
+  /// 连续失败的次数，connected 时为这次连接成功前失败的次数
+  pub attempt: u32,
+  /// reconnecting 时距离下一次重连的等待时间（毫秒）
+  pub delay: Option<u32>,
+  /// 触发重连或者放弃重连的原因
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct HeartbeatConfig {
+  enabled: bool,
+  auto_reply: bool,
+  interval: Duration,
+  max_missed: u32,
+  ping: Vec<u8>,
+  pong: Vec<u8>,
+  // 按 compression 选项编码后实际发送的 ping / pong
+  ping_wire: Vec<u8>,
+  pong_wire: Vec<u8>,
+}
+
+impl HeartbeatConfig {
+  // 只有心跳和自动回复都关闭时才返回 None
+  fn from_options(opt: &SocketOptions) -> Result<Option<Self>> {
+    let enabled = opt.enable_heartbeat.unwrap_or(false);
+    let auto_reply = opt.heartbeat_auto_reply.unwrap_or(false);
+    if !enabled && !auto_reply {
+      return Ok(None);
+    }
+    opt.protocol().ensure("heartbeat", HEARTBEAT_PROTOCOLS)?;
+    let ping = opt
+      .heartbeat_payload
+      .clone()
+      .unwrap_or_else(|| "__nng_ping__".to_string())
+      .into_bytes();
+    let pong = opt
+      .heartbeat_reply_payload
+      .clone()
+      .unwrap_or_else(|| "__nng_pong__".to_string())
+      .into_bytes();
+    Ok(Some(HeartbeatConfig {
+      enabled,
+      auto_reply,
+      interval: Duration::from_millis(
+        opt
+          .heartbeat_interval
+          .and_then(|i| i.try_into().ok())
+          .unwrap_or(3000),
+      ),
+      max_missed: opt.heartbeat_max_missed.unwrap_or(3).max(1),
+      ping_wire: opt.encode(&ping)?.as_slice().to_vec(),
+      pong_wire: opt.encode(&pong)?.as_slice().to_vec(),
+      ping,
+      pong,
+    }))
+  }
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+pub struct SurveyOptions {
+  /// 本次调查的截止时间（毫秒），默认使用 SocketOptions.surveyTime
+  pub deadline_ms: Option<i32>,
+}
+
+impl SocketOptions {
+  fn protocol(&self) -> SocketProtocol {
+    self.protocol.unwrap_or(SocketProtocol::Pair1)
+  }
+
+  fn recv_timeout(&self) -> Duration {
+    Duration::from_millis(
+      self
+        .recv_timeout
+        .and_then(|i| i.try_into().ok())
+        .unwrap_or(5000), // 5秒接收超时，提高响应性
+    )
+  }
+
+  // non_blocking_dial 时立即返回，连接由 nng 在后台建立，之后的 send 会等待连接可用（受 sendTimeout 限制）
+  fn dial(&self, client: &nng::Socket, url: &str) -> Result<()> {
+    self.start_dialer(client, url, self.non_blocking_dial.unwrap_or(false))
+  }
+
+  // 失败时错误只包含原因，由调用方加上前缀
+  fn start_dialer(&self, client: &nng::Socket, url: &str, non_blocking: bool) -> Result<()> {
+    let url = &self.resolve_url(url);
+    let dial_error = |e| Error::from_reason(tls::describe_error(e, url));
+    let dialer = nng::DialerBuilder::new(client, url).map_err(dial_error)?;
+    if tls::is_tls_url(url) {
+      tls::configure_dialer(self.tls.as_ref(), &dialer, url)?;
+    }
+    if let Some(ws) = self.web_socket(url) {
+      ws.configure_dialer(&dialer)?;
+    }
+    dialer
+      .start(non_blocking)
+      .map(|_| ())
+      .map_err(|(_, e)| dial_error(e))
+  }
+
+  // webSocket 选项只对 ws:// 和 wss:// 地址生效
+  fn web_socket(&self, url: &str) -> Option<&WebSocketOptions> {
+    self
+      .web_socket
+      .as_ref()
+      .filter(|_| websocket::is_websocket_url(url))
+  }
+
+  fn resolve_url(&self, url: &str) -> String {
+    self
+      .web_socket(url)
+      .map_or_else(|| url.to_string(), |ws| ws.url(url))
+  }
+
+  fn listen(&self, client: &nng::Socket, url: &str) -> Result<()> {
+    let url = &self.resolve_url(url);
+    let listen_error = |e| Error::from_reason(tls::describe_error(e, url));
+    // 没有传 ipc 选项时同样按默认值清理残留的 socket 文件
+    let listener = if url.starts_with("ipc://") {
+      self.ipc.clone().unwrap_or_default().listener(client, url)
+    } else {
+      nng::ListenerBuilder::new(client, url)
+    }
+    .map_err(listen_error)?;
+    if tls::is_tls_url(url) {
+      tls::configure_listener(self.tls.as_ref(), &listener, url)?;
+    }
+    if let Some(ws) = self.web_socket(url) {
+      ws.configure_listener(&listener)?;
+    }
+    listener
+      .start()
+      .map(|_| ())
+      .map_err(|(_, e)| listen_error(e))
+  }
+
+  fn send_timeout(&self) -> Duration {
+    Duration::from_millis(
+      self
+        .send_timeout
+        .and_then(|i| i.try_into().ok())
+        .unwrap_or(5000), // 5秒发送超时
+    )
+  }
+
+  fn encode(&self, payload: &[u8]) -> Result<nng::Message> {
+    encode_message(self.compression.as_ref(), payload)
+  }
+
+  fn decode(&self, msg: &nng::Message) -> Result<Vec<u8>> {
+    decode_message(self.compression.as_ref(), msg)
+  }
+}
+
+// 开启 compression 时按需压缩并加上压缩标记，否则原样发送
+fn encode_message(
+  compression: Option<&CompressionOptions>,
+  payload: &[u8],
+) -> Result<nng::Message> {
+  match compression {
+    Some(compression) => compression
+      .encode(payload)
+      .map(|data| nng::Message::from(&data[..])),
+    None => Ok(nng::Message::from(payload)),
+  }
+}
+
+fn decode_message(compression: Option<&CompressionOptions>, msg: &nng::Message) -> Result<Vec<u8>> {
+  match compression {
+    Some(_) => decode_payload(msg.as_slice()),
+    None => Ok(msg.as_slice().to_vec()),
+  }
+}
+
+#[napi(object)]
+#[derive(Clone, Debug)]
+pub struct PipeEventInfo {
+  /// nng 分配的连接 id，同一个连接的 pipeAdded / pipeRemoved 事件 id 相同
+  pub id: u32,
+  /// 对端地址，例如 tcp://127.0.0.1:50000
+  pub remote_address: Option<String>,
+  /// IPC 连接对端进程的用户 id，其他传输方式为空
+  pub peer_uid: Option<u32>,
+  /// IPC 连接对端进程的用户组 id
+  pub peer_gid: Option<u32>,
+  /// IPC 连接对端的进程 id
+  pub peer_pid: Option<u32>,
+  /// WebSocket 连接握手请求的路径（包括查询参数），其他传输方式为空
+  pub request_uri: Option<String>,
+  /// WebSocket 连接握手请求的 HTTP 头，listen 端可以据此校验 Authorization、Cookie 等
+  pub request_headers: Option<HashMap<String, String>>,
+}
+
+impl PipeEventInfo {
+  fn new(id: u32, pipe: nng::Pipe) -> Self {
+    let peer = PeerCredentials::of(pipe);
+    let request = WebSocketRequest::of(pipe);
+    PipeEventInfo {
+      id,
+      remote_address: pipe.get_opt::<RemAddr>().ok().map(|a| a.to_string()),
+      peer_uid: peer.uid,
+      peer_gid: peer.gid,
+      peer_pid: peer.pid,
+      request_uri: request.uri,
+      request_headers: request.headers,
+    }
+  }
+}
+
+#[napi(object)]
+pub struct ReceivedMessage {
+  pub payload: Buffer,
+  /// 消息来自的连接（pipe）id，与 pipeAdded / pipeRemoved 事件中的 id 一致
+  pub pipe_id: Option<u32>,
+  pub local_address: Option<String>,
+  pub remote_address: Option<String>,
+  /// 传输方式，取自连接地址的协议部分，例如 tcp、ipc、inproc、ws
+  pub transport: Option<String>,
+  /// IPC 连接对端进程的用户 id、用户组 id 和进程 id，其他传输方式为空
+  pub peer_uid: Option<u32>,
+  pub peer_gid: Option<u32>,
+  pub peer_pid: Option<u32>,
+}
+
+impl ReceivedMessage {
+  fn new(payload: Buffer, metadata: MessageMetadata) -> Self {
+    ReceivedMessage {
+      payload,
+      pipe_id: metadata.pipe_id,
+      local_address: metadata.local_address,
+      remote_address: metadata.remote_address,
+      transport: metadata.transport,
+      peer_uid: metadata.peer.uid,
+      peer_gid: metadata.peer.gid,
+      peer_pid: metadata.peer.pid,
+    }
+  }
+}
+
+// 消息来自的连接信息，在接收线程上读取，之后连接可能随时断开
+struct MessageMetadata {
+  pipe_id: Option<u32>,
+  local_address: Option<String>,
+  remote_address: Option<String>,
+  transport: Option<String>,
+  peer: PeerCredentials,
+}
+
+impl MessageMetadata {
+  fn of(msg: &mut nng::Message) -> Self {
+    let pipe = msg.pipe();
+    // 连接可能已经断开，这时只能拿到 pipe id
+    MessageMetadata {
+      pipe_id: pipe.map(pipe_id),
+      local_address: pipe.and_then(|p| p.get_opt::<LocalAddr>().ok().map(|a| a.to_string())),
+      remote_address: pipe.and_then(|p| p.get_opt::<RemAddr>().ok().map(|a| a.to_string())),
+      transport: pipe.and_then(pipe_transport),
+      peer: pipe.map(PeerCredentials::of).unwrap_or_default(),
+    }
+  }
+}
+
+// 传输方式，取自 pipe 所属 dialer / listener 地址的协议部分
+fn pipe_transport(pipe: nng::Pipe) -> Option<String> {
+  let url = match (pipe.dialer(), pipe.listener()) {
+    (Some(dialer), _) => dialer.get_opt::<Url>().ok(),
+    (_, Some(listener)) => listener.get_opt::<Url>().ok(),
+    _ => None,
+  };
+  url.and_then(|u| u.split_once("://").map(|(scheme, _)| scheme.to_string()))
+}
+
+fn pipe_id(pipe: nng::Pipe) -> u32 {
+  // SAFETY: nng_pipe_id 只读取句柄中的 id，句柄失效时返回 -1
+  unsafe { nng_sys::nng_pipe_id(pipe.nng_pipe()) as u32 }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PipeEventKind {
+  Added,
+  Removed,
+}
+
+impl PipeEventKind {
+  fn name(self) -> &'static str {
+    match self {
+      PipeEventKind::Added => "pipeAdded",
+      PipeEventKind::Removed => "pipeRemoved",
+    }
+  }
+}
+
+type PipeEventCallback = ThreadsafeFunction<(PipeEventKind, PipeEventInfo), ErrorStrategy::Fatal>;
+
+// Socket 上当前存活的连接（pipe）以及连接事件的回调，由 nng 的 pipe notify 回调更新
+#[derive(Default)]
+struct PipeEvents {
+  // pipe id -> (pipe, 连接信息)，连接移除后 pipe 已经不能再读取选项，所以在建立时记下来
+  pipes: Mutex<HashMap<u32, (nng::Pipe, PipeEventInfo)>>,
+  // 按对端凭据过滤 IPC 连接
+  ipc: Option<IpcOptions>,
+  // nng.js 注册的回调，再由 EventEmitter 分发给各个监听器
+  watcher: Mutex<Option<PipeEventCallback>>,
+}
+
+impl PipeEvents {
+  // 在 nng 的线程上调用，不能 panic
+  fn notify(&self, pipe: nng::Pipe, event: nng::PipeEvent) {
+    let id = pipe_id(pipe);
+    let (kind, info) = match event {
+      // AddPre 不通知 JS：这里需要同步给出结果，而 JS 线程可能正阻塞在触发这个连接的 connect 中
+      nng::PipeEvent::AddPre => {
+        // 关闭后 nng 不会再触发 AddPost，对方看到的是连接立即断开
+        if let Some(ipc) = &self.ipc {
+          if !ipc.accepts(pipe, pipe_transport(pipe).as_deref()) {
+            pipe.close();
+          }
+        }
+        return;
+      }
+      nng::PipeEvent::AddPost => {
+        let info = PipeEventInfo::new(id, pipe);
+        let Ok(mut pipes) = self.pipes.lock() else {
+          return;
+        };
+        pipes.insert(id, (pipe, info.clone()));
+        (PipeEventKind::Added, info)
+      }
+      nng::PipeEvent::RemovePost => {
+        let Ok(mut pipes) = self.pipes.lock() else {
+          return;
+        };
+        // 在 AddPre 阶段被拒绝的连接不会出现在 pipes 中，也不需要通知
+        match pipes.remove(&id) {
+          Some((_, info)) => (PipeEventKind::Removed, info),
+          None => return,
+        }
+      }
+      _ => return,
+    };
+
+    let Ok(watcher) = self.watcher.lock() else {
+      return;
+    };
+    if let Some(callback) = watcher.as_ref() {
+      callback.call((kind, info), ThreadsafeFunctionCallMode::NonBlocking);
+    }
+  }
+
+  fn connected(&self) -> bool {
+    !self.pipes.lock().unwrap().is_empty()
+  }
+
+  fn pipe(&self, id: u32) -> Option<nng::Pipe> {
+    self.pipes.lock().unwrap().get(&id).map(|(pipe, _)| *pipe)
+  }
+}
+
+impl std::fmt::Debug for PipeEvents {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("PipeEvents")
+      .field("pipes", &self.pipes)
+      .finish_non_exhaustive()
+  }
+}
+
+// startReceiving 启动的接收线程，running 在线程退出时（包括连接出错）置为 false
+struct SocketReceiver {
+  disposable: MessageRecvDisposable,
+  running: Arc<AtomicBool>,
+}
+
+impl SocketReceiver {
+  // onMessage 返回的 disposable 由 JS 持有，dispose 之后即使线程还没有退出也不再占用接收槽
+  fn is_running(&self) -> bool {
+    self.running.load(Ordering::Relaxed) && !self.disposable.control.is_stopped()
+  }
+}
+
+impl std::fmt::Debug for SocketReceiver {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("SocketReceiver")
+      .field("running", &self.is_running())
+      .finish_non_exhaustive()
+  }
 }
 
 #[napi]
 #[derive(Clone, Debug)]
 pub struct Socket {
   client: nng::Socket,
-  connected: bool,
+  events: Arc<PipeEvents>,
+  receiver: Arc<Mutex<Option<SocketReceiver>>>,
   pub options: SocketOptions,
 }
 
@@ -40,73 +675,439 @@ impl Socket {
   #[napi(constructor)]
   pub fn new(options: Option<SocketOptions>) -> Result<Self> {
     let opt = options.unwrap_or_default();
+    let client = Self::create_client(&opt)?;
+    let events = Arc::new(PipeEvents {
+      ipc: opt.ipc.clone(),
+      ..Default::default()
+    });
+    let notify = events.clone();
+    client
+      .pipe_notify(move |pipe, event| notify.notify(pipe, event))
+      .map_err(|e| Error::from_reason(format!("Register pipe notify failed: {}", e)))?;
     Ok(Socket {
-      client: Self::create_client(&opt)?,
-      connected: false,
+      client,
+      events,
+      receiver: Arc::default(),
       options: opt,
     })
   }
 
   pub fn create_client(opt: &SocketOptions) -> Result<nng::Socket> {
-    nng::Socket::new(Protocol::Pair1)
-      .map(|client| {
-        let _ = client.set_opt::<RecvTimeout>(Some(Duration::from_millis(
-          opt
-            .recv_timeout
-            .and_then(|i| i.try_into().ok())
-            .unwrap_or(5000), // 5秒接收超时，提高响应性
-        )));
-        let _ = client.set_opt::<SendTimeout>(Some(Duration::from_millis(
-          opt
-            .send_timeout
-            .and_then(|i| i.try_into().ok())
-            .unwrap_or(5000), // 5秒发送超时
-        )));
-        client
-      })
-      .map_err(|e| Error::from_reason(format!("Initiate socket failed: {}", e)))
+    let client = nng::Socket::new(opt.protocol().into())
+      .map_err(|e| Error::from_reason(format!("Initiate socket failed: {}", e)))?;
+    if opt.compression.is_some() {
+      opt
+        .protocol()
+        .ensure("compression", COMPRESSION_PROTOCOLS)?;
+    }
+    let _ = client.set_opt::<RecvTimeout>(Some(opt.recv_timeout()));
+    let _ = client.set_opt::<SendTimeout>(Some(opt.send_timeout()));
+    if let Some(interval) = opt.resend_interval {
+      client
+        .set_opt::<ResendTime>(Some(Duration::from_millis(
+          interval.try_into().unwrap_or_default(),
+        )))
+        .map_err(|e| Error::from_reason(format!("Set resend interval failed: {}", e)))?;
+    }
+    if let Some(survey_time) = opt.survey_time {
+      client
+        .set_opt::<SurveyTime>(Some(Duration::from_millis(
+          survey_time.try_into().unwrap_or_default(),
+        )))
+        .map_err(|e| Error::from_reason(format!("Set survey time failed: {}", e)))?;
+    }
+    if opt.polyamorous.unwrap_or(false) {
+      opt
+        .protocol()
+        .ensure("polyamorous", &[SocketProtocol::Pair1])?;
+      // nng 已经标记为废弃，但目前仍是 Pair1 同时连接多个对端的唯一方式
+      #[allow(deprecated)]
+      client
+        .set_opt::<nng::options::protocol::pair::Polyamorous>(true)
+        .map_err(|e| Error::from_reason(format!("Set polyamorous failed: {}", e)))?;
+    }
+    if let Some(min_time) = opt.reconnect_min_time {
+      client
+        .set_opt::<ReconnectMinTime>(Some(Duration::from_millis(
+          min_time.try_into().unwrap_or_default(),
+        )))
+        .map_err(|e| Error::from_reason(format!("Set reconnect min time failed: {}", e)))?;
+    }
+    if let Some(max_time) = opt.reconnect_max_time {
+      client
+        .set_opt::<ReconnectMaxTime>(Some(Duration::from_millis(
+          max_time.try_into().unwrap_or_default(),
+        )))
+        .map_err(|e| Error::from_reason(format!("Set reconnect max time failed: {}", e)))?;
+    }
+    Ok(client)
   }
 
   #[napi]
-  pub fn connect(&mut self, url: String) -> Result<()> {
-    let ret = self
-      .client
-      .dial(&url)
-      .map_err(|e| Error::from_reason(format!("Connect {} failed: {}", url, e)));
-    self.connected = ret.is_ok();
-    ret
+  pub fn connect(&self, url: String) -> Result<()> {
+    self
+      .options
+      .dial(&self.client, &url)
+      .map_err(|e| Error::from_reason(format!("Connect {} failed: {}", url, e.reason)))
+  }
+
+  #[napi]
+  pub fn listen(&self, url: String) -> Result<()> {
+    self
+      .options
+      .listen(&self.client, &url)
+      .map_err(|e| Error::from_reason(format!("Listen {} failed: {}", url, e.reason)))
   }
 
   #[napi]
   pub fn send(&self, req: Buffer) -> Result<Buffer> {
-    let msg = nng::Message::from(&req[..]);
+    self.options.protocol().ensure("send", REQUEST_PROTOCOLS)?;
+    let reply = Self::round_trip(&self.client, self.options.encode(&req)?)?;
+    self.options.decode(&reply).map(Buffer::from)
+  }
+
+  // 只发送不等待回复（Push 等单向协议）
+  #[napi]
+  pub fn send_only(&self, msg: Buffer) -> Result<()> {
+    self.options.protocol().ensure("sendOnly", SEND_PROTOCOLS)?;
     self
       .client
-      .send(msg)
-      .map_err(|(_, e)| Error::from_reason(format!("Send rpc failed: {}", e)))?;
+      .send(self.options.encode(&msg)?)
+      .map_err(|(_, e)| Error::from_reason(format!("Send failed: {}", e)))
+  }
+  // Pair1 多对端（polyamorous）模式下把消息发给指定的连接，pipeId 来自 ReceivedMessage 或 pipeAdded 事件
+  #[napi]
+  pub fn send_to(&self, pipe_id: u32, msg: Buffer) -> Result<()> {
     self
+      .options
+      .protocol()
+      .ensure("sendTo", &[SocketProtocol::Pair1])?;
+    let pipe = self
+      .events
+      .pipe(pipe_id)
+      .ok_or_else(|| Error::from_reason(format!("Pipe {} is not connected", pipe_id)))?;
+    let mut msg = self.options.encode(&msg)?;
+    msg.set_pipe(pipe);
+    self
+      .client
+      .send(msg)
+      .map_err(|(_, e)| Error::from_reason(format!("Send failed: {}", e)))
+  }
+
+  // 非阻塞发送：对端暂时无法接收时返回 false，而不是等待发送超时
+  #[napi]
+  pub fn try_send(&self, msg: Buffer) -> Result<bool> {
+    self.options.protocol().ensure("trySend", SEND_PROTOCOLS)?;
+    match self.client.try_send(self.options.encode(&msg)?) {
+      Ok(()) => Ok(true),
+      Err((_, nng::Error::TryAgain)) => Ok(false),
+      Err((_, e)) => Err(Error::from_reason(format!("Send failed: {}", e))),
+    }
+  }
+
+  // 单独接收一条消息，阻塞直到收到消息或接收超时
+  #[napi]
+  pub fn recv(&self) -> Result<Buffer> {
+    self.options.protocol().ensure("recv", RECV_PROTOCOLS)?;
+    self.ensure_not_receiving("recv")?;
+    let msg = self
       .client
       .recv()
-      .map(|msg| msg.as_slice().into())
+      .map_err(|e| Error::from_reason(format!("Recv failed: {}", e)))?;
+    self.options.decode(&msg).map(Buffer::from)
+  }
+
+  // 异步接收：由 nng AIO 回调 settle Promise，等待期间不占用 libuv 线程池
+  #[napi(ts_return_type = "Promise<Buffer>")]
+  pub fn recv_async(&self, env: Env) -> Result<JsObject> {
+    self
+      .options
+      .protocol()
+      .ensure("recvAsync", RECV_PROTOCOLS)?;
+    self.ensure_not_receiving("recvAsync")?;
+    let (deferred, promise) = env.create_deferred()?;
+    AioExchange::start(
+      AioTarget::Socket(self.client.clone()),
+      None,
+      self.options.compression.clone(),
+      self.options.send_timeout(),
+      self.options.recv_timeout(),
+      false,
+      ("Send failed", "Recv failed"),
+      Box::new(move |res| settle_reply(deferred, res)),
+    );
+    Ok(promise)
+  }
+
+  // 非阻塞接收：当前没有消息时返回 null
+  #[napi]
+  pub fn try_recv(&self) -> Result<Option<Buffer>> {
+    self.options.protocol().ensure("tryRecv", RECV_PROTOCOLS)?;
+    self.ensure_not_receiving("tryRecv")?;
+    match self.client.try_recv() {
+      Ok(msg) => self
+        .options
+        .decode(&msg)
+        .map(|payload| Some(payload.into())),
+      Err(nng::Error::TryAgain) => Ok(None),
+      Err(e) => Err(Error::from_reason(format!("Recv failed: {}", e))),
+    }
+  }
+
+  // 异步版本的 send：收发由 nng AIO 回调驱动，不阻塞事件循环也不占用 libuv 线程池，超时沿用 SocketOptions
+  #[napi(ts_return_type = "Promise<Buffer>")]
+  pub fn send_async(&self, env: Env, req: Buffer) -> Result<JsObject> {
+    let protocol = self.options.protocol();
+    protocol.ensure("sendAsync", REQUEST_PROTOCOLS)?;
+    let (deferred, promise) = env.create_deferred()?;
+    let finish: AioFinish = Box::new(move |res| settle_reply(deferred, res));
+    let req = match self.options.encode(&req) {
+      Ok(req) => req,
+      Err(e) => {
+        finish(Err(e));
+        return Ok(promise);
+      }
+    };
+    // 每个请求独占一个 Context，多个并发请求的回复由 nng 按请求 ID 分别匹配
+    let target = if protocol == SocketProtocol::Req {
+      match Context::new(&self.client) {
+        Ok(ctx) => AioTarget::Context(ctx),
+        Err(e) => {
+          finish(Err(Error::from_reason(format!(
+            "Create context failed: {}",
+            e
+          ))));
+          return Ok(promise);
+        }
+      }
+    } else {
+      AioTarget::Socket(self.client.clone())
+    };
+    AioExchange::start(
+      target,
+      Some(req),
+      self.options.compression.clone(),
+      self.options.send_timeout(),
+      self.options.recv_timeout(),
+      false,
+      ("Send rpc failed", "Recv rpc failed"),
+      finish,
+    );
+    Ok(promise)
+  }
+
+  // Surveyor：广播调查，收集截止时间之前所有 Respondent 的回复
+  #[napi(ts_return_type = "Promise<Array<Buffer>>")]
+  pub fn survey(&self, env: Env, req: Buffer, options: Option<SurveyOptions>) -> Result<JsObject> {
+    self
+      .options
+      .protocol()
+      .ensure("survey", &[SocketProtocol::Surveyor])?;
+    let (deferred, promise) = env.create_deferred()?;
+    let finish: AioFinish = Box::new(move |res| match res {
+      Ok(responses) => deferred.resolve(Box::new(move |_| {
+        Ok(responses.into_iter().map(Buffer::from).collect::<Vec<_>>())
+      })),
+      Err(e) => deferred.reject(e),
+    });
+    let deadline = options
+      .and_then(|o| o.deadline_ms)
+      .map(|ms| Duration::from_millis(ms.try_into().unwrap_or_default()));
+    let setup = || -> Result<(Context, Duration, nng::Message)> {
+      let ctx = Context::new(&self.client)
+        .map_err(|e| Error::from_reason(format!("Create context failed: {}", e)))?;
+      if let Some(deadline) = deadline {
+        ctx
+          .set_opt::<SurveyTime>(Some(deadline))
+          .map_err(|e| Error::from_reason(format!("Set survey time failed: {}", e)))?;
+      }
+      let deadline = ctx
+        .get_opt::<SurveyTime>()
+        .ok()
+        .flatten()
+        .unwrap_or(self.options.recv_timeout());
+      Ok((ctx, deadline, self.options.encode(&req)?))
+    };
+    match setup() {
+      Ok((ctx, deadline, req)) => AioExchange::start(
+        AioTarget::Context(ctx),
+        Some(req),
+        self.options.compression.clone(),
+        self.options.send_timeout(),
+        deadline,
+        true,
+        ("Send survey failed", "Recv survey failed"),
+        finish,
+      ),
+      Err(e) => finish(Err(e)),
+    }
+    Ok(promise)
+  }
+
+  fn round_trip(client: &nng::Socket, req: nng::Message) -> Result<nng::Message> {
+    client
+      .send(req)
+      .map_err(|(_, e)| Error::from_reason(format!("Send rpc failed: {}", e)))?;
+    client
+      .recv()
       .map_err(|e| Error::from_reason(format!("Recv rpc failed: {}", e)))
   }
 
+  #[napi]
+  pub fn publish(&self, msg: Buffer) -> Result<()> {
+    self
+      .options
+      .protocol()
+      .ensure("publish", &[SocketProtocol::Pub])?;
+    self
+      .client
+      .send(self.options.encode(&msg)?)
+      .map_err(|(_, e)| Error::from_reason(format!("Publish failed: {}", e)))
+  }
+
+  #[napi]
+  pub fn subscribe(&self, topic: Either<Buffer, String>) -> Result<()> {
+    subscribe(self.options.protocol(), &self.client, &topic_bytes(topic))
+  }
+
+  #[napi]
+  pub fn unsubscribe(&self, topic: Either<Buffer, String>) -> Result<()> {
+    unsubscribe(self.options.protocol(), &self.client, &topic_bytes(topic))
+  }
+
   #[napi]
   pub fn close(&mut self) {
+    // 停止 startReceiving / onMessage 的接收线程，线程退出后触发 close 事件
+    if let Some(mut receiver) = self.receiver.lock().unwrap().take() {
+      let _ = receiver.disposable.dispose();
+    }
     self.client.close();
-    self.connected = false;
+    // 关闭时触发的 pipeRemoved 已经发出，释放回调
+    self.events.watcher.lock().unwrap().take();
   }
 
+  // 当前是否至少有一个存活的连接（包括 listen 接受的连接）
   #[napi]
   pub fn connected(&self) -> bool {
-    self.connected
+    self.events.connected()
+  }
+
+  // 连接建立 / 断开时以 (事件名, 连接信息) 调用 callback，由 nng.js 在注册第一个
+  // pipeAdded / pipeRemoved 监听器时调用并转成 EventEmitter 事件；回调不会阻止 Node.js 进程退出
+  #[napi(
+    js_name = "_watchPipes",
+    ts_args_type = "callback: (event: 'pipeAdded' | 'pipeRemoved', pipe: PipeEventInfo) => void"
+  )]
+  pub fn watch_pipes(&self, env: Env, callback: JsFunction) -> Result<()> {
+    let mut callback: PipeEventCallback = callback.create_threadsafe_function(
+      0,
+      |ctx: ThreadSafeCallContext<(PipeEventKind, PipeEventInfo)>| {
+        let (kind, info) = ctx.value;
+        Ok(vec![Either::A(kind.name().to_string()), Either::B(info)])
+      },
+    )?;
+    callback.unref(&env)?;
+    *self.events.watcher.lock().unwrap() = Some(callback);
+    Ok(())
+  }
+
+  // 在当前 Socket 上启动接收线程，消息交给 callback，线程退出时调用 onClose；
+  // nng.js 把它们转成 on('message' | 'error' | 'close') 事件。
+  // 接收线程和 send 共用同一个 nng Socket，因此一个 Pair1 连接就可以双向通信
+  #[napi(
+    ts_args_type = "callback: (err: null | Error, bytes: Buffer, message?: ReceivedMessage) => void, onClose: () => void"
+  )]
+  pub fn start_receiving(&self, callback: JsFunction, on_close: JsFunction) -> Result<()> {
+    self
+      .options
+      .protocol()
+      .ensure("startReceiving", RECV_PROTOCOLS)?;
+    let (callback, queue) = message_callback(callback, &self.options)?;
+    let on_close: ThreadsafeFunction<(), ErrorStrategy::Fatal> = on_close
+      .create_threadsafe_function(0, |_: ThreadSafeCallContext<()>| Ok(Vec::<u32>::new()))?;
+    let on_exit: RecvExitHook = Box::new(move || {
+      on_close.call((), ThreadsafeFunctionCallMode::NonBlocking);
+    });
+    self.spawn_socket_receiver(MessageSink::Callback(callback, queue), on_exit)?;
+    Ok(())
+  }
+
+  // startReceiving 和 onMessage 共用一个接收槽：同一个 Socket 上同时只能有一个接收线程，
+  // 否则消息会被几个接收方随机瓜分。接收槽由 stopReceiving / close 释放
+  fn spawn_socket_receiver(
+    &self,
+    sink: MessageSink,
+    on_exit: RecvExitHook,
+  ) -> Result<MessageRecvDisposable> {
+    let mut receiver = self.receiver.lock().unwrap();
+    if receiver.as_ref().is_some_and(SocketReceiver::is_running) {
+      return Err(Error::new(
+        Status::GenericFailure,
+        "Already receiving, call stopReceiving() or dispose the onMessage receiver first"
+          .to_string(),
+      ));
+    }
+    let running = Arc::new(AtomicBool::new(true));
+    let exited = running.clone();
+    let on_exit: RecvExitHook = Box::new(move || {
+      exited.store(false, Ordering::Relaxed);
+      on_exit();
+    });
+    let disposable = spawn_recv_loop(
+      self.client.clone(),
+      &self.options,
+      sink,
+      false,
+      Some(on_exit),
+    )?;
+    *receiver = Some(SocketReceiver {
+      disposable: disposable.share(),
+      running,
+    });
+    Ok(disposable)
+  }
+
+  // 接收线程运行期间 recv 会和它抢消息，直接报错
+  fn ensure_not_receiving(&self, method: &str) -> Result<()> {
+    if self.is_receiving() {
+      return Err(Error::new(
+        Status::GenericFailure,
+        format!(
+          "{} is not available while startReceiving() or onMessage() is receiving",
+          method
+        ),
+      ));
+    }
+    Ok(())
+  }
+
+  // 停止 startReceiving 或 onMessage 启动的接收线程，不关闭 Socket；正在进行的接收立即取消，
+  // 返回后旧的线程不会再从 nng 取消息，可以马上重新启动。线程退出后触发 close 事件
+  #[napi]
+  pub fn stop_receiving(&self) {
+    if let Some(mut receiver) = self.receiver.lock().unwrap().take() {
+      // 连接出错时线程已经退出，停止信号发送失败可以忽略
+      let _ = receiver.disposable.dispose();
+    }
+  }
+
+  // startReceiving 或 onMessage 启动的接收线程是否还在运行
+  #[napi]
+  pub fn is_receiving(&self) -> bool {
+    self
+      .receiver
+      .lock()
+      .unwrap()
+      .as_ref()
+      .is_some_and(SocketReceiver::is_running)
   }
 
   // 静态方法：测试连接是否可用
   #[napi]
   pub fn test_connection(url: String, options: Option<SocketOptions>) -> Result<bool> {
-    let client = Self::create_client(&options.unwrap_or_default())?;
-    match client.dial(&url) {
+    let options = options.unwrap_or_default();
+    let client = Self::create_client(&options)?;
+    match options.start_dialer(&client, &url, false) {
       Ok(_) => {
         client.close();
         Ok(true)
@@ -115,79 +1116,444 @@ impl Socket {
     }
   }
 
-  #[napi(ts_args_type = "callback: (err: null | Error, bytes: Buffer) => void")]
+  // 配置了 reconnect 时在后台线程中连接，断开后自动重连，onStatus 接收连接状态的变化
+  #[napi(
+    ts_args_type = "url: string, callback: (err: null | Error, bytes: Buffer, message?: ReceivedMessage) => void, options?: SocketOptions | undefined | null, onStatus?: (status: ReconnectStatus) => void"
+  )]
   pub fn recv_message(
     url: String,
-    callback: ThreadsafeFunction<Buffer, ErrorStrategy::CalleeHandled>,
+    callback: JsFunction,
     options: Option<SocketOptions>,
+    on_status: Option<ThreadsafeFunction<ReconnectStatus, ErrorStrategy::Fatal>>,
   ) -> Result<MessageRecvDisposable> {
-    let client = Self::create_client(&options.unwrap_or_default())?;
-    client
-      .dial(&url)
-      .map_err(|e| Error::new(Status::GenericFailure, format!("Failed to connect: {}", e)))?;
-    let (tx, rx) = mpsc::channel::<()>();
-    let connection_alive = Arc::new(AtomicBool::new(true));
-    let connection_alive_clone = connection_alive.clone();
+    let options = options.unwrap_or_default();
+    options.protocol().ensure("recvMessage", RECV_PROTOCOLS)?;
+    let (callback, queue) = message_callback(callback, &options)?;
+    let sink = MessageSink::Callback(callback, queue);
+    Self::spawn_receiver(url, options, sink, on_status)
+  }
 
-    thread::spawn(move || {
-      loop {
-        // 检查是否需要停止
-        if rx.try_recv().is_ok() {
-          connection_alive_clone.store(false, Ordering::Relaxed);
-          client.close();
-          break;
-        }
+  // 异步迭代器版本的 recvMessage：for await 读取消息（Symbol.asyncIterator 定义在 nng.js 中），
+  // JS 处理不过来时接收线程暂停从 nng 取消息，退出循环（break / return / 抛出异常）时自动 dispose
+  #[napi]
+  pub fn messages(url: String, options: Option<SocketOptions>) -> Result<MessageStream> {
+    let options = options.unwrap_or_default();
+    options.protocol().ensure("messages", RECV_PROTOCOLS)?;
+    let queue = Arc::new(MessageQueue::new(
+      options
+        .max_queue_size
+        .map_or(MESSAGE_STREAM_CAPACITY, |size| size.max(1) as usize),
+    ));
+    let writer = Arc::new(MessageQueueWriter(queue.clone()));
+    let disposable = Self::spawn_receiver(url, options, MessageSink::Queue(writer), None)?;
+    Ok(MessageStream { queue, disposable })
+  }
 
-        match client.recv() {
-          Ok(msg) => {
-            let call_result = callback.clone().call(
-              Ok(msg.as_slice().into()),
-              ThreadsafeFunctionCallMode::NonBlocking,
-            );
+  fn spawn_receiver(
+    url: String,
+    options: SocketOptions,
+    sink: MessageSink,
+    on_status: Option<ThreadsafeFunction<ReconnectStatus, ErrorStrategy::Fatal>>,
+  ) -> Result<MessageRecvDisposable> {
+    if options.reconnect.is_some() {
+      return Reconnector::spawn(url, options, sink, on_status);
+    }
+    let client = Self::create_client(&options)?;
+    options.dial(&client, &url).map_err(|e| {
+      Error::new(
+        Status::GenericFailure,
+        format!("Failed to connect: {}", e.reason),
+      )
+    })?;
+    spawn_recv_loop(client, &options, sink, true, None)
+  }
 
-            // 如果 Node.js 正在关闭，立即退出
-            if matches!(call_result, napi::Status::Closing) {
-              connection_alive_clone.store(false, Ordering::Relaxed);
-              client.close();
-              return;
-            }
+  // 在当前 Socket 上启动接收线程（例如 Bus 协议下 listen/dial 之后接收所有对端的消息），
+  // 与 startReceiving 共用接收槽；dispose 只停止接收，不关闭 Socket
+  #[napi(
+    ts_args_type = "callback: (err: null | Error, bytes: Buffer, message?: ReceivedMessage) => void"
+  )]
+  pub fn on_message(&self, callback: JsFunction) -> Result<MessageRecvDisposable> {
+    self
+      .options
+      .protocol()
+      .ensure("onMessage", RECV_PROTOCOLS)?;
+    let (callback, queue) = message_callback(callback, &self.options)?;
+    self.spawn_socket_receiver(MessageSink::Callback(callback, queue), Box::new(|| {}))
+  }
+
+  // 服务端：每个请求交给 handler 处理，handler 返回（或 resolve）的 Buffer 作为回复。
+  // handler 必须返回 Promise（同步抛出的异常无法从 ThreadsafeFunction 回调中恢复），
+  // nng.js 中的 serve 会把用户的 handler 包装成总是返回 Promise 的函数
+  #[napi(
+    ts_args_type = "handler: (err: null | Error, req: Buffer) => Promise<Buffer>, concurrency?: number"
+  )]
+  pub fn serve(
+    &self,
+    handler: JsFunction,
+    concurrency: Option<u32>,
+  ) -> Result<MessageRecvDisposable> {
+    let protocol = self.options.protocol();
+    protocol.ensure("serve", SERVE_PROTOCOLS)?;
+    let handler: ThreadsafeFunction<Buffer, ErrorStrategy::CalleeHandled> = handler
+      .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Buffer>| Ok(vec![ctx.value]))?;
+    let connection_alive = Arc::new(AtomicBool::new(true));
+    let mut disposable = MessageRecvDisposable::new(
+      Vec::new(),
+      connection_alive.clone(),
+      self.client.clone(),
+      protocol,
+      None,
+    );
+
+    for _ in 0..concurrency.unwrap_or(SERVE_CONCURRENCY).max(1) {
+      let ctx = Context::new(&self.client)
+        .map_err(|e| Error::from_reason(format!("Create context failed: {}", e)))?;
+      // 用 Aio 收发，dispose 时可以立即取消，不用等到 recvTimeout / sendTimeout
+      let (results_tx, results) = mpsc::channel();
+      let aio = Aio::new(move |_, res| {
+        let _ = results_tx.send(res);
+      })
+      .map_err(|e| Error::from_reason(format!("Create aio failed: {}", e)))?;
+      let (tx, rx) = mpsc::channel::<()>();
+      disposable.txs.push(tx);
+      let handler = handler.clone();
+      let connection_alive = connection_alive.clone();
+      let control = disposable.control.clone();
+      let send_timeout = self.options.send_timeout();
+      let recv_timeout = self.options.recv_timeout();
+      let compression = self.options.compression.clone();
+
+      thread::spawn(move || loop {
+        // handler 所在的 JS 环境（Worker 或 Node.js）退出后同样停止
+        if rx.try_recv().is_ok() || handler.aborted() {
+          connection_alive.store(false, Ordering::Relaxed);
+          return;
+        }
+
+        let (busy, received) = match aio
+          .set_timeout(Some(recv_timeout))
+          .and_then(|_| control.begin(&aio, |aio| ctx.recv(aio)))
+        {
+          Ok(Some(busy)) => match results.recv() {
+            Ok(AioResult::Recv(received)) => (Some(busy), received),
+            _ => (Some(busy), Err(nng::Error::IncorrectState)),
+          },
+          Ok(None) => return,
+          Err(e) => (None, Err(e)),
+        };
+        let msg = match received {
+          Ok(msg) => msg,
+          Err(nng::Error::TimedOut) => continue,
+          // dispose 取消了接收，或者 Socket 已经关闭
+          Err(nng::Error::Canceled) | Err(nng::Error::Closed) => {
+            connection_alive.store(false, Ordering::Relaxed);
+            return;
           }
-          Err(e) => match e {
-            nng::Error::Closed => {
-              connection_alive_clone.store(false, Ordering::Relaxed);
-              return;
-            }
-            nng::Error::TimedOut => continue, // 超时是正常的，继续循环
-            _ => {
-              // 其他错误，通知客户端并退出
-              connection_alive_clone.store(false, Ordering::Relaxed);
-              let _ = callback.clone().call(
-                Err(Error::new(
-                  Status::GenericFailure,
-                  format!("Connection lost: {}", e),
-                )),
-                ThreadsafeFunctionCallMode::NonBlocking,
-              );
+          Err(e) => {
+            connection_alive.store(false, Ordering::Relaxed);
+            handler.call_with_return_value(
+              Err(Error::new(
+                Status::GenericFailure,
+                format!("Connection lost: {}", e),
+              )),
+              ThreadsafeFunctionCallMode::NonBlocking,
+              |_: Promise<Buffer>| Ok(()),
+            );
+            return;
+          }
+        };
+        // 接收完成时 dispose 可能已经开始，这时不再把请求交给 handler
+        if control.is_stopped() {
+          return;
+        }
+
+        // 无法解压的请求直接丢弃，由请求方超时或重发
+        let req = match decode_message(compression.as_ref(), &msg) {
+          Ok(req) => req,
+          Err(_) => continue,
+        };
+        let (reply_tx, reply_rx) = mpsc::channel::<Vec<u8>>();
+        let call_result = handler.call_with_return_value(
+          Ok(req.into()),
+          ThreadsafeFunctionCallMode::NonBlocking,
+          move |reply: Promise<Buffer>| {
+            spawn(async move {
+              if let Ok(reply) = reply.await {
+                let _ = reply_tx.send(reply.to_vec());
+              }
+            });
+            Ok(())
+          },
+        );
+        drop(busy);
+        if matches!(call_result, napi::Status::Closing) {
+          connection_alive.store(false, Ordering::Relaxed);
+          return;
+        }
+
+        // 等待 handler 给出回复；handler 抛错或 reject 时丢弃该请求，由请求方超时或重发
+        let reply = loop {
+          match reply_rx.recv_timeout(recv_timeout) {
+            Ok(reply) => break Some(reply),
+            Err(RecvTimeoutError::Timeout) if control.is_stopped() => {
+              connection_alive.store(false, Ordering::Relaxed);
               return;
             }
-          },
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break None,
+          }
+        };
+        if let Some(reply) = reply.and_then(|r| encode_message(compression.as_ref(), &r).ok()) {
+          // dispose 之后不再发送回复，正在发送的回复会被取消
+          let sending = aio
+            .set_timeout(Some(send_timeout))
+            .and_then(|_| control.begin(&aio, |aio| ctx.send(aio, reply).map_err(|(_, e)| e)));
+          if let Ok(Some(_busy)) = sending {
+            let _ = results.recv();
+          }
         }
-      }
+      });
+    }
+
+    Ok(disposable)
+  }
+}
+
+type AioFinish = Box<dyn FnOnce(Result<Vec<Vec<u8>>>) + Send>;
+
+type ReplyDeferred = JsDeferred<Buffer, Box<dyn FnOnce(Env) -> Result<Buffer> + Send>>;
+
+fn settle_reply(deferred: ReplyDeferred, res: Result<Vec<Vec<u8>>>) {
+  match res.map(|mut replies| replies.pop().unwrap_or_default()) {
+    Ok(reply) => deferred.resolve(Box::new(move |_| Ok(reply.into()))),
+    Err(e) => deferred.reject(e),
+  }
+}
+
+enum AioTarget {
+  Socket(nng::Socket),
+  Context(Context),
+}
+
+impl AioTarget {
+  fn send(&self, aio: &Aio, msg: nng::Message) -> nng::Result<()> {
+    match self {
+      AioTarget::Socket(socket) => socket.send_async(aio, msg),
+      AioTarget::Context(ctx) => ctx.send(aio, msg),
+    }
+    .map_err(|(_, e)| e)
+  }
+
+  fn recv(&self, aio: &Aio) -> nng::Result<()> {
+    match self {
+      AioTarget::Socket(socket) => socket.recv_async(aio),
+      AioTarget::Context(ctx) => ctx.recv(aio),
+    }
+  }
+}
+
+// sendAsync / recvAsync / survey 的一次收发：每一步都在 AIO 回调里发起下一步，
+// 等待期间不占用任何线程，结束时在回调里 settle Promise
+struct AioExchange {
+  target: AioTarget,
+  compression: Option<CompressionOptions>,
+  recv_timeout: Duration,
+  // survey 一直接收到截止时间，其余操作收到一条回复就结束
+  collect: bool,
+  // 发送、接收失败时的错误前缀
+  errors: (&'static str, &'static str),
+  state: Mutex<AioExchangeState>,
+}
+
+struct AioExchangeState {
+  // 操作进行中由这里持有 AIO，结束后交给 AIO_REAPER 释放
+  aio: Option<Aio>,
+  replies: Vec<Vec<u8>>,
+  finish: Option<AioFinish>,
+}
+
+impl AioExchange {
+  #[allow(clippy::too_many_arguments)]
+  fn start(
+    target: AioTarget,
+    req: Option<nng::Message>,
+    compression: Option<CompressionOptions>,
+    send_timeout: Duration,
+    recv_timeout: Duration,
+    collect: bool,
+    errors: (&'static str, &'static str),
+    finish: AioFinish,
+  ) {
+    let exchange = Arc::new(AioExchange {
+      target,
+      compression,
+      recv_timeout,
+      collect,
+      errors,
+      state: Mutex::new(AioExchangeState {
+        aio: None,
+        replies: Vec::new(),
+        finish: Some(finish),
+      }),
     });
+    let callback = exchange.clone();
+    let aio = match Aio::new(move |aio, res| callback.on_complete(aio, res)) {
+      Ok(aio) => aio,
+      Err(e) => {
+        exchange.fail(exchange.errors.1, e);
+        return;
+      }
+    };
+    // 先保存 AIO 再发起操作，回调可能在发起之后立即执行
+    exchange.state.lock().unwrap().aio = Some(aio.clone());
+    let started = match req {
+      Some(req) => aio
+        .set_timeout(Some(send_timeout))
+        .and_then(|_| exchange.target.send(&aio, req))
+        .map_err(|e| (exchange.errors.0, e)),
+      None => aio
+        .set_timeout(Some(recv_timeout))
+        .and_then(|_| exchange.target.recv(&aio))
+        .map_err(|e| (exchange.errors.1, e)),
+    };
+    if let Err((prefix, e)) = started {
+      exchange.fail(prefix, e);
+      exchange.release(aio);
+    }
+  }
 
-    Ok(MessageRecvDisposable {
-      closed: false,
-      tx,
-      connection_alive,
-    })
+  fn on_complete(&self, aio: Aio, res: AioResult) {
+    let next = match res {
+      AioResult::Send(Ok(())) => aio
+        .set_timeout(Some(self.recv_timeout))
+        .and_then(|_| self.target.recv(&aio))
+        .map_err(|e| (self.errors.1, e)),
+      AioResult::Send(Err((_, e))) => Err((self.errors.0, e)),
+      AioResult::Recv(Ok(msg)) => {
+        match decode_message(self.compression.as_ref(), &msg) {
+          Ok(payload) => self.state.lock().unwrap().replies.push(payload),
+          Err(e) => {
+            self.finish(Err(e));
+            return self.release(aio);
+          }
+        }
+        if !self.collect {
+          self.succeed();
+          return self.release(aio);
+        }
+        self.target.recv(&aio).map_err(|e| (self.errors.1, e))
+      }
+      // 调查截止，返回已收集到的回复
+      AioResult::Recv(Err(nng::Error::TimedOut)) if self.collect => {
+        self.succeed();
+        return self.release(aio);
+      }
+      AioResult::Recv(Err(e)) => Err((self.errors.1, e)),
+      AioResult::Sleep(_) => Err((self.errors.1, nng::Error::IncorrectState)),
+    };
+    if let Err((prefix, e)) = next {
+      self.fail(prefix, e);
+      self.release(aio);
+    }
   }
+
+  fn succeed(&self) {
+    let replies = std::mem::take(&mut self.state.lock().unwrap().replies);
+    self.finish(Ok(replies));
+  }
+
+  fn fail(&self, prefix: &str, e: nng::Error) {
+    self.finish(Err(Error::from_reason(format!("{}: {}", prefix, e))));
+  }
+
+  fn finish(&self, res: Result<Vec<Vec<u8>>>) {
+    if let Some(finish) = self.state.lock().unwrap().finish.take() {
+      finish(res);
+    }
+  }
+
+  // 释放 AIO 时 nng 会等待回调返回，不能在回调里释放最后一个引用，交给 AIO_REAPER 线程
+  fn release(&self, aio: Aio) {
+    let held = self.state.lock().unwrap().aio.take();
+    let _ = aio_reaper().send((aio, held));
+  }
+}
+
+// 回调收到的 AIO 和 AioExchangeState 里保存的 AIO
+type ReleasedAio = (Aio, Option<Aio>);
+
+static AIO_REAPER: OnceLock<Mutex<Sender<ReleasedAio>>> = OnceLock::new();
+
+fn aio_reaper() -> Sender<ReleasedAio> {
+  AIO_REAPER
+    .get_or_init(|| {
+      let (tx, rx) = mpsc::channel::<ReleasedAio>();
+      thread::spawn(move || for _ in rx {});
+      Mutex::new(tx)
+    })
+    .lock()
+    .unwrap()
+    .clone()
 }
 
 #[napi]
 pub struct MessageRecvDisposable {
   closed: bool,
-  tx: Sender<()>,
+  txs: Vec<Sender<()>>,
   connection_alive: Arc<AtomicBool>,
+  // 自动重连时接收线程会换成新的 Socket
+  client: Arc<Mutex<nng::Socket>>,
+  // 已订阅的主题，重连后在新的 Socket 上恢复
+  topics: Arc<Mutex<Vec<Vec<u8>>>>,
+  protocol: SocketProtocol,
+  // recvMessage / onMessage 的回调队列，serve 没有
+  queue: Option<Arc<RecvQueue>>,
+  control: Arc<RecvControl>,
+}
+
+impl MessageRecvDisposable {
+  fn new(
+    txs: Vec<Sender<()>>,
+    connection_alive: Arc<AtomicBool>,
+    client: nng::Socket,
+    protocol: SocketProtocol,
+    queue: Option<Arc<RecvQueue>>,
+  ) -> Self {
+    MessageRecvDisposable {
+      closed: false,
+      txs,
+      connection_alive,
+      client: Arc::new(Mutex::new(client)),
+      topics: Arc::new(Mutex::new(Vec::new())),
+      protocol,
+      queue,
+      control: Arc::default(),
+    }
+  }
+
+  // 与 JS 持有的 disposable 停止同一组接收线程，Socket 的接收槽用它来 stopReceiving / close
+  fn share(&self) -> Self {
+    MessageRecvDisposable {
+      closed: self.closed,
+      txs: self.txs.clone(),
+      connection_alive: self.connection_alive.clone(),
+      client: self.client.clone(),
+      topics: self.topics.clone(),
+      protocol: self.protocol,
+      queue: self.queue.clone(),
+      control: self.control.clone(),
+    }
+  }
+
+  fn recv_queue(&self, method: &str) -> Result<&RecvQueue> {
+    self.queue.as_deref().ok_or_else(|| {
+      Error::new(
+        Status::InvalidArg,
+        format!("{} is only supported by recvMessage / onMessage", method),
+      )
+    })
+  }
 }
 
 #[napi]
@@ -196,10 +1562,17 @@ impl MessageRecvDisposable {
   pub fn dispose(&mut self) -> Result<()> {
     if !self.closed {
       self.connection_alive.store(false, Ordering::Relaxed);
-      self
-        .tx
-        .send(())
-        .map_err(|e| Error::from_reason(format!("Failed to stop msg channel: {}", e)))?;
+      // 先停止接收线程再返回：取消正在进行的接收，唤醒因为暂停或者队列已满而等待的接收线程，
+      // 已经从 nng 取出的消息放进队列后仍会交给回调，之后不会再有新的消息
+      self.control.stop();
+      if let Some(queue) = &self.queue {
+        queue.stop();
+      }
+      self.control.wait_idle();
+      // 接收被取消后线程可能已经自行退出，发送失败说明它已经停止
+      for tx in &self.txs {
+        let _ = tx.send(());
+      }
       self.closed = true;
     }
     Ok(())
@@ -215,15 +1588,967 @@ impl MessageRecvDisposable {
   pub fn is_connection_alive(&self) -> bool {
     self.connection_alive.load(Ordering::Relaxed)
   }
+
+  // 暂停接收：接收线程不再从 nng 取消息，已经取出的消息仍会交给回调
+  #[napi]
+  pub fn pause(&self) -> Result<()> {
+    self.recv_queue("pause")?.set_paused(true);
+    Ok(())
+  }
+
+  #[napi]
+  pub fn resume(&self) -> Result<()> {
+    self.recv_queue("resume")?.set_paused(false);
+    Ok(())
+  }
+
+  // 按 queuePolicy 丢弃的消息数
+  #[napi]
+  pub fn dropped_count(&self) -> Result<u32> {
+    Ok(
+      self
+        .recv_queue("droppedCount")?
+        .dropped
+        .load(Ordering::Relaxed),
+    )
+  }
+
+  // Sub 协议：在接收线程运行期间动态增减订阅的主题
+  #[napi]
+  pub fn subscribe(&self, topic: Either<Buffer, String>) -> Result<()> {
+    let topic = topic_bytes(topic);
+    let client = self.client.lock().unwrap();
+    subscribe(self.protocol, &client, &topic)?;
+    self.topics.lock().unwrap().push(topic);
+    Ok(())
+  }
+
+  #[napi]
+  pub fn unsubscribe(&self, topic: Either<Buffer, String>) -> Result<()> {
+    let topic = topic_bytes(topic);
+    let client = self.client.lock().unwrap();
+    unsubscribe(self.protocol, &client, &topic)?;
+    let mut topics = self.topics.lock().unwrap();
+    if let Some(index) = topics.iter().position(|t| *t == topic) {
+      topics.remove(index);
+    }
+    Ok(())
+  }
+}
+
+// Socket.messages 返回的异步可迭代对象
+#[napi]
+pub struct MessageStream {
+  queue: Arc<MessageQueue>,
+  disposable: MessageRecvDisposable,
 }
 
 #[napi]
-pub fn lz4_compress(input: Buffer) -> Result<Buffer> {
-  match compress(&input, Some(CompressionMode::DEFAULT), false) {
-    Ok(compressed) => Ok(Buffer::from(compressed)),
-    Err(e) => Err(Error::new(
+impl MessageStream {
+  // 取下一条消息，dispose 之后或者接收线程已经退出时返回 null
+  #[napi(ts_return_type = "Promise<Buffer | null>")]
+  pub fn recv(&self, env: Env) -> Result<JsObject> {
+    let (deferred, promise) = env.create_deferred()?;
+    self.queue.pop(deferred);
+    Ok(promise)
+  }
+
+  #[napi]
+  pub fn dispose(&mut self) {
+    // 先关闭队列，等待队列空间的接收线程才能退出，disposable.dispose 不会一直等下去
+    self.queue.close(true);
+    // 连接出错后接收线程已经退出，停止信号发送失败不影响释放
+    let _ = self.disposable.dispose();
+    self.disposable.closed = true;
+  }
+
+  #[napi]
+  pub fn is_closed(&self) -> bool {
+    self.disposable.is_closed()
+  }
+
+  #[napi]
+  pub fn is_connection_alive(&self) -> bool {
+    self.disposable.is_connection_alive()
+  }
+
+  #[napi]
+  pub fn subscribe(&self, topic: Either<Buffer, String>) -> Result<()> {
+    self.disposable.subscribe(topic)
+  }
+
+  #[napi]
+  pub fn unsubscribe(&self, topic: Either<Buffer, String>) -> Result<()> {
+    self.disposable.unsubscribe(topic)
+  }
+}
+
+// 没有 dispose 就被回收（包括所在的 Worker 退出）时同样停止接收线程
+impl Drop for MessageStream {
+  fn drop(&mut self) {
+    self.queue.abandon();
+  }
+}
+
+type MessageDeferred =
+  JsDeferred<Option<Buffer>, Box<dyn FnOnce(Env) -> Result<Option<Buffer>> + Send>>;
+
+// messages() 的消息队列：接收线程写入，recv 取出。队列满时接收线程等待，不再从 nng 取消息；
+// 在 JS 线程之外等待消息，不占用 libuv 线程池
+struct MessageQueue {
+  state: Mutex<MessageQueueState>,
+  space: Condvar,
+  capacity: usize,
+}
+
+#[derive(Default)]
+struct MessageQueueState {
+  items: VecDeque<Result<Vec<u8>>>,
+  // 还没有等到消息的 recv
+  waiting: VecDeque<MessageDeferred>,
+  closed: bool,
+}
+
+impl MessageQueue {
+  fn new(capacity: usize) -> Self {
+    MessageQueue {
+      state: Mutex::new(MessageQueueState::default()),
+      space: Condvar::new(),
+      capacity,
+    }
+  }
+
+  // 返回 false 表示队列已经关闭，接收线程应当退出
+  fn push(&self, item: Result<Vec<u8>>) -> bool {
+    let mut state = self.state.lock().unwrap();
+    loop {
+      if state.closed {
+        return false;
+      }
+      if let Some(deferred) = state.waiting.pop_front() {
+        settle(deferred, Some(item));
+        return true;
+      }
+      if state.items.len() < self.capacity {
+        state.items.push_back(item);
+        return true;
+      }
+      state = self.space.wait(state).unwrap();
+    }
+  }
+
+  fn is_full(&self) -> bool {
+    self.state.lock().unwrap().items.len() >= self.capacity
+  }
+
+  fn is_closed(&self) -> bool {
+    self.state.lock().unwrap().closed
+  }
+
+  // JS 环境可能已经销毁，只唤醒接收线程让它退出，不再 settle 等待中的 recv
+  fn abandon(&self) {
+    self.state.lock().unwrap().closed = true;
+    self.space.notify_all();
+  }
+
+  fn pop(&self, deferred: MessageDeferred) {
+    let mut state = self.state.lock().unwrap();
+    if let Some(item) = state.items.pop_front() {
+      self.space.notify_one();
+      settle(deferred, Some(item));
+    } else if state.closed {
+      settle(deferred, None);
+    } else {
+      state.waiting.push_back(deferred);
+    }
+  }
+
+  // 接收线程退出时保留已经收到的消息，dispose 时一起丢弃
+  fn close(&self, discard: bool) {
+    let mut state = self.state.lock().unwrap();
+    state.closed = true;
+    if discard {
+      state.items.clear();
+    }
+    for deferred in state.waiting.drain(..) {
+      settle(deferred, None);
+    }
+    self.space.notify_all();
+  }
+}
+
+fn settle(deferred: MessageDeferred, item: Option<Result<Vec<u8>>>) {
+  match item {
+    Some(Ok(payload)) => deferred.resolve(Box::new(move |_| Ok(Some(payload.into())))),
+    Some(Err(e)) => deferred.reject(e),
+    None => deferred.resolve(Box::new(|_| Ok(None))),
+  }
+}
+
+// 接收线程和心跳线程共用的写入端，全部释放（线程都已退出）时关闭队列
+struct MessageQueueWriter(Arc<MessageQueue>);
+
+impl Drop for MessageQueueWriter {
+  fn drop(&mut self) {
+    self.0.close(false);
+  }
+}
+
+// 接收回调的参数：消息内容，开启 messageMetadata 时再追加一个 ReceivedMessage
+type MessageArgs = Vec<Either<Buffer, ReceivedMessage>>;
+
+// 接收线程交给 JS 线程的一条消息，回调参数在 JS 线程上创建
+struct IncomingMessage {
+  payload: Vec<u8>,
+  metadata: Option<MessageMetadata>,
+}
+
+impl IncomingMessage {
+  // 回调的 bytes 和 message.payload 是同一个 JS Buffer，消息内容只在从 nng 取出时复制一次
+  fn into_args(self, env: Env) -> Result<MessageArgs> {
+    let Some(metadata) = self.metadata else {
+      return Ok(vec![Either::A(self.payload.into())]);
+    };
+    let payload = env.create_buffer_with_data(self.payload)?.into_raw();
+    // SAFETY: 在 JS 线程的回调中调用，payload 是刚创建的 Buffer，每个 Buffer 各自持有一个对它的引用
+    let shared = || unsafe { Buffer::from_napi_value(env.raw(), payload.raw()) };
+    Ok(vec![
+      Either::A(shared()?),
+      Either::B(ReceivedMessage::new(shared()?, metadata)),
+    ])
+  }
+}
+
+// 每次调用只是通知 JS 线程从 RecvQueue 中取一条，消息本身放在 RecvQueue 中，以便按策略丢弃
+type MessageCallback = ThreadsafeFunction<(), ErrorStrategy::CalleeHandled>;
+
+fn message_callback(
+  callback: JsFunction,
+  options: &SocketOptions,
+) -> Result<(MessageCallback, Arc<RecvQueue>)> {
+  let queue = Arc::new(RecvQueue::new(options));
+  let pending = RecvQueueGuard(queue.clone());
+  let callback = callback.create_threadsafe_function(0, move |ctx: ThreadSafeCallContext<()>| {
+    pending.0.pop()?.into_args(ctx.env)
+  })?;
+  Ok((callback, queue))
+}
+
+// 由回调的 TSFN 持有，TSFN 被销毁（所在的 Worker 或 Node.js 退出）后不会再有人取消息，
+// 关闭队列让暂停或者等待队列空间的接收线程退出
+struct RecvQueueGuard(Arc<RecvQueue>);
+
+impl Drop for RecvQueueGuard {
+  fn drop(&mut self) {
+    self.0.close();
+  }
+}
+
+// recvMessage / onMessage 积压在 JS 线程上待处理的消息
+struct RecvQueue {
+  state: Mutex<RecvQueueState>,
+  changed: Condvar,
+  max_size: Option<usize>,
+  policy: QueuePolicy,
+  dropped: AtomicU32,
+}
+
+#[derive(Default)]
+struct RecvQueueState {
+  items: VecDeque<Result<IncomingMessage>>,
+  paused: bool,
+  // 已经 dispose，只接收停止前从 nng 取出的消息
+  stopping: bool,
+  closed: bool,
+}
+
+impl RecvQueue {
+  fn new(options: &SocketOptions) -> Self {
+    RecvQueue {
+      state: Mutex::new(RecvQueueState::default()),
+      changed: Condvar::new(),
+      max_size: options.max_queue_size.map(|size| size.max(1) as usize),
+      policy: options.queue_policy.unwrap_or(QueuePolicy::Block),
+      dropped: AtomicU32::new(0),
+    }
+  }
+
+  // 返回 false 表示不再接收；返回 true 且 notify 为 true 时需要通知 JS 线程取一条
+  fn push(&self, item: Result<IncomingMessage>) -> (bool, bool) {
+    let mut state = self.state.lock().unwrap();
+    if state.closed {
+      return (false, false);
+    }
+    // dispose 之前取出的消息不受队列上限和策略限制
+    if state.stopping {
+      state.items.push_back(item);
+      return (false, true);
+    }
+    let full = |state: &RecvQueueState| self.max_size.is_some_and(|max| state.items.len() >= max);
+    // 错误总是交给 JS，不受队列上限限制
+    if item.is_ok() && full(&state) {
+      match self.policy {
+        QueuePolicy::Block => {
+          while full(&state) && !state.closed && !state.stopping {
+            state = self.changed.wait(state).unwrap();
+          }
+          if state.closed {
+            return (false, false);
+          }
+        }
+        QueuePolicy::DropNewest => {
+          self.dropped.fetch_add(1, Ordering::Relaxed);
+          return (true, false);
+        }
+        // 替换掉最旧的一条，队列长度和已经发出的通知数不变
+        QueuePolicy::DropOldest => {
+          let index = state.items.iter().position(|item| item.is_ok());
+          if let Some(index) = index {
+            state.items.remove(index);
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+          }
+          state.items.push_back(item);
+          return (true, index.is_none());
+        }
+        QueuePolicy::Error => {
+          self.dropped.fetch_add(1, Ordering::Relaxed);
+          state.items.push_back(Err(Error::new(
+            Status::GenericFailure,
+            format!(
+              "Receive queue full: more than {} messages pending",
+              self.max_size.unwrap_or_default()
+            ),
+          )));
+          return (false, true);
+        }
+      }
+    }
+    state.items.push_back(item);
+    (!state.stopping, true)
+  }
+
+  fn pop(&self) -> Result<IncomingMessage> {
+    let item = self.state.lock().unwrap().items.pop_front();
+    self.changed.notify_all();
+    item.unwrap_or_else(|| Err(Error::from_reason("Receive queue is empty".to_string())))
+  }
+
+  // 暂停期间不再从 nng 取消息；返回 false 表示已经 dispose
+  fn wait_resumed(&self) -> bool {
+    let mut state = self.state.lock().unwrap();
+    while state.paused && !state.closed && !state.stopping {
+      state = self.changed.wait(state).unwrap();
+    }
+    !state.closed && !state.stopping
+  }
+
+  // 接收线程因为暂停或者队列已满而没有取消息，这时收不到心跳不代表对端断开
+  fn is_stalled(&self) -> bool {
+    let state = self.state.lock().unwrap();
+    state.paused
+      || (self.policy == QueuePolicy::Block
+        && self.max_size.is_some_and(|max| state.items.len() >= max))
+  }
+
+  fn set_paused(&self, paused: bool) {
+    self.state.lock().unwrap().paused = paused;
+    self.changed.notify_all();
+  }
+
+  fn stop(&self) {
+    self.state.lock().unwrap().stopping = true;
+    self.changed.notify_all();
+  }
+
+  fn close(&self) {
+    self.state.lock().unwrap().closed = true;
+    self.changed.notify_all();
+  }
+}
+
+// 接收线程和 dispose 共用的停止状态。dispose 取消正在进行的接收，并等已经从 nng 取出的消息
+// 交给 JS 后才返回，这样停止时不会丢消息，停止之后也不会再有消息交给回调。
+// serve 的每个 worker 各自登记自己的 AIO 操作
+#[derive(Default)]
+struct RecvControl {
+  state: Mutex<RecvControlState>,
+  idle: Condvar,
+}
+
+#[derive(Default)]
+struct RecvControlState {
+  stopped: bool,
+  // 还没有结束的操作：AIO 没有完成，或者收到的消息还没有交给 JS
+  busy: Vec<(u64, Aio)>,
+  next_id: u64,
+  // 自动重连的会话编号，旧 Socket 关闭时迟到的断开通知不影响新的会话
+  session: u64,
+  // 当前会话的对端已经断开
+  peer_lost: bool,
+}
+
+impl RecvControl {
+  // 发起一次 AIO 操作，已经停止时返回 None；返回的 RecvBusy 在操作结果处理完后释放
+  fn begin<F>(&self, aio: &Aio, start: F) -> nng::Result<Option<RecvBusy<'_>>>
+  where
+    F: FnOnce(&Aio) -> nng::Result<()>,
+  {
+    let mut state = self.state.lock().unwrap();
+    if state.stopped {
+      return Ok(None);
+    }
+    if state.peer_lost {
+      return Err(nng::Error::ConnectionReset);
+    }
+    start(aio)?;
+    let id = state.next_id;
+    state.next_id += 1;
+    state.busy.push((id, aio.clone()));
+    Ok(Some(RecvBusy(self, id)))
+  }
+
+  fn recv(&self, client: &nng::Socket, aio: &Aio) -> nng::Result<Option<RecvBusy<'_>>> {
+    self.begin(aio, |aio| client.recv_async(aio))
+  }
+
+  // 被取消的操作以 Canceled 结束；取消前已经收到的消息照常交给 JS
+  fn stop(&self) {
+    let mut state = self.state.lock().unwrap();
+    state.stopped = true;
+    for (_, aio) in &state.busy {
+      aio.cancel();
+    }
+  }
+
+  fn is_stopped(&self) -> bool {
+    self.state.lock().unwrap().stopped
+  }
+
+  // 开始新的重连会话，返回会话编号
+  fn new_session(&self) -> u64 {
+    let mut state = self.state.lock().unwrap();
+    state.session += 1;
+    state.peer_lost = false;
+    state.session
+  }
+
+  // 对端断开：取消正在进行的接收，让接收线程以 Lost 结束并重连；不需要等到心跳超时
+  fn lose_peer(&self, session: u64) {
+    let mut state = self.state.lock().unwrap();
+    if state.session != session || state.stopped {
+      return;
+    }
+    state.peer_lost = true;
+    for (_, aio) in &state.busy {
+      aio.cancel();
+    }
+  }
+
+  fn is_peer_lost(&self) -> bool {
+    let state = self.state.lock().unwrap();
+    state.peer_lost && !state.stopped
+  }
+
+  fn wait_idle(&self) {
+    let mut state = self.state.lock().unwrap();
+    while !state.busy.is_empty() {
+      state = self.idle.wait(state).unwrap();
+    }
+  }
+}
+
+struct RecvBusy<'a>(&'a RecvControl, u64);
+
+impl Drop for RecvBusy<'_> {
+  fn drop(&mut self) {
+    let mut state = self.0.state.lock().unwrap();
+    state.busy.retain(|(id, _)| *id != self.1);
+    self.0.idle.notify_all();
+  }
+}
+
+// 接收线程把消息和错误交给 JS 的方式
+#[derive(Clone)]
+enum MessageSink {
+  // recvMessage / onMessage 的回调
+  Callback(MessageCallback, Arc<RecvQueue>),
+  // messages() 的有界队列
+  Queue(Arc<MessageQueueWriter>),
+}
+
+impl MessageSink {
+  // 返回 false 表示 JS 侧已经不再接收（dispose、Node.js 正在退出或迭代器已经释放）
+  fn message(&self, payload: Vec<u8>, msg: &mut nng::Message, metadata: bool) -> bool {
+    match self {
+      MessageSink::Callback(..) => self.deliver(Ok(IncomingMessage {
+        payload,
+        metadata: metadata.then(|| MessageMetadata::of(msg)),
+      })),
+      MessageSink::Queue(writer) => writer.0.push(Ok(payload)),
+    }
+  }
+
+  fn error(&self, error: Error) -> bool {
+    match self {
+      MessageSink::Callback(..) => self.deliver(Err(error)),
+      MessageSink::Queue(writer) => writer.0.push(Err(error)),
+    }
+  }
+
+  fn deliver(&self, item: Result<IncomingMessage>) -> bool {
+    let MessageSink::Callback(callback, queue) = self else {
+      return false;
+    };
+    let (receiving, notify) = queue.push(item);
+    if notify {
+      let call_result = callback.call(Ok(()), ThreadsafeFunctionCallMode::NonBlocking);
+      if matches!(call_result, napi::Status::Closing) {
+        return false;
+      }
+    }
+    receiving
+  }
+
+  fn wait_resumed(&self) -> bool {
+    match self {
+      MessageSink::Callback(_, queue) => queue.wait_resumed(),
+      MessageSink::Queue(writer) => !writer.0.is_closed(),
+    }
+  }
+
+  fn is_stalled(&self) -> bool {
+    match self {
+      MessageSink::Callback(_, queue) => queue.is_stalled(),
+      MessageSink::Queue(writer) => writer.0.is_full(),
+    }
+  }
+
+  fn recv_queue(&self) -> Option<Arc<RecvQueue>> {
+    match self {
+      MessageSink::Callback(_, queue) => Some(queue.clone()),
+      MessageSink::Queue(_) => None,
+    }
+  }
+}
+
+// 一次接收会话结束的原因
+enum RecvExit {
+  // dispose 或 Node.js 正在退出
+  Stopped,
+  // 接收出错，连接已不可用
+  Lost(nng::Error),
+}
+
+// 接收线程退出时调用，用于 startReceiving 的 close 事件
+type RecvExitHook = Box<dyn FnOnce() + Send>;
+
+// 接收线程：持续接收消息并通过回调交给 JS，close_on_exit 决定停止时是否顺带关闭 Socket
+fn spawn_recv_loop(
+  client: nng::Socket,
+  options: &SocketOptions,
+  sink: MessageSink,
+  close_on_exit: bool,
+  on_exit: Option<RecvExitHook>,
+) -> Result<MessageRecvDisposable> {
+  let heartbeat = HeartbeatConfig::from_options(options)?;
+  let options = options.clone();
+  let (tx, rx) = mpsc::channel::<()>();
+  let connection_alive = Arc::new(AtomicBool::new(true));
+  let last_seen = Arc::new(Mutex::new(Instant::now()));
+
+  // 心跳线程在接收线程退出（dispose 或者连接出错）时随 heartbeat_tx 一起退出
+  let heartbeat_tx = heartbeat.clone().filter(|h| h.enabled).map(|heartbeat| {
+    let (tx, rx) = mpsc::channel::<()>();
+    spawn_heartbeat(
+      client.clone(),
+      heartbeat,
+      rx,
+      sink.clone(),
+      connection_alive.clone(),
+      last_seen.clone(),
+      false,
+    );
+    tx
+  });
+
+  let disposable = MessageRecvDisposable::new(
+    vec![tx],
+    connection_alive.clone(),
+    client.clone(),
+    options.protocol(),
+    sink.recv_queue(),
+  );
+  let control = disposable.control.clone();
+
+  thread::spawn(move || {
+    let exit = recv_session(
+      &client,
+      heartbeat.as_ref(),
+      &rx,
+      &control,
+      &sink,
+      &connection_alive,
+      &last_seen,
+      &options,
+    );
+    connection_alive.store(false, Ordering::Relaxed);
+    drop(heartbeat_tx);
+    if let RecvExit::Lost(e) = exit {
+      // Socket 关闭以外的错误，通知客户端并退出
+      if e != nng::Error::Closed {
+        sink.error(Error::new(
+          Status::GenericFailure,
+          format!("Connection lost: {}", e),
+        ));
+      }
+    }
+    // recvMessage 自己创建的 Socket 不会再被用到，连接出错时同样关闭
+    if close_on_exit {
+      client.close();
+    }
+    if let Some(on_exit) = on_exit {
+      on_exit();
+    }
+  });
+
+  Ok(disposable)
+}
+
+// 在当前线程上接收消息，直到收到停止信号或者连接出错
+#[allow(clippy::too_many_arguments)]
+fn recv_session(
+  client: &nng::Socket,
+  heartbeat: Option<&HeartbeatConfig>,
+  rx: &mpsc::Receiver<()>,
+  control: &RecvControl,
+  sink: &MessageSink,
+  connection_alive: &AtomicBool,
+  last_seen: &Mutex<Instant>,
+  options: &SocketOptions,
+) -> RecvExit {
+  // 用 Aio 接收，dispose 时可以立即取消，不用等到 recvTimeout
+  let (tx, results) = mpsc::channel();
+  let aio = match Aio::new(move |_, res| {
+    let _ = tx.send(res);
+  })
+  .and_then(|aio| aio.set_timeout(Some(options.recv_timeout())).map(|_| aio))
+  {
+    Ok(aio) => aio,
+    Err(e) => return RecvExit::Lost(e),
+  };
+
+  loop {
+    // 检查是否需要停止（dispose，或者回调所在的 Worker / Node.js 已经退出）
+    if rx.try_recv().is_ok() || !sink.wait_resumed() {
+      return RecvExit::Stopped;
+    }
+    let _busy = match control.recv(client, &aio) {
+      Ok(Some(busy)) => busy,
+      Ok(None) => return RecvExit::Stopped,
+      Err(e) => return RecvExit::Lost(e),
+    };
+    let received = match results.recv() {
+      Ok(AioResult::Recv(received)) => received,
+      _ => Err(nng::Error::IncorrectState),
+    };
+
+    match received {
+      Ok(mut msg) => {
+        if heartbeat.is_some() {
+          *last_seen.lock().unwrap() = Instant::now();
+          connection_alive.store(true, Ordering::Relaxed);
+        }
+        // 无法解压的消息不影响连接，把错误交给回调后继续接收
+        let payload = match options.decode(&msg) {
+          Ok(payload) => payload,
+          Err(e) => {
+            if !sink.error(e) {
+              return RecvExit::Stopped;
+            }
+            continue;
+          }
+        };
+        if let Some(heartbeat) = heartbeat {
+          // 心跳消息只用于保活，不交给 JS
+          if payload == heartbeat.ping {
+            if heartbeat.auto_reply {
+              let _ = client.try_send(nng::Message::from(&heartbeat.pong_wire[..]));
+            }
+            continue;
+          }
+          if payload == heartbeat.pong {
+            continue;
+          }
+        }
+
+        // 如果 Node.js 正在关闭，立即退出
+        if !sink.message(payload, &mut msg, options.message_metadata.unwrap_or(false)) {
+          return RecvExit::Stopped;
+        }
+      }
+      Err(nng::Error::TimedOut) => continue, // 超时是正常的，继续循环
+      Err(nng::Error::Canceled) if control.is_peer_lost() => {
+        return RecvExit::Lost(nng::Error::ConnectionReset)
+      }
+      Err(nng::Error::Canceled) => return RecvExit::Stopped,
+      Err(e) => return RecvExit::Lost(e),
+    }
+  }
+}
+
+// 自动重连的接收线程：连接断开后按退避策略换一个新的 Socket 重新连接，disposable 始终不变
+struct Reconnector {
+  url: String,
+  options: SocketOptions,
+  policy: ReconnectOptions,
+  heartbeat: Option<HeartbeatConfig>,
+  client: Arc<Mutex<nng::Socket>>,
+  topics: Arc<Mutex<Vec<Vec<u8>>>>,
+  connection_alive: Arc<AtomicBool>,
+  last_seen: Arc<Mutex<Instant>>,
+  control: Arc<RecvControl>,
+  sink: MessageSink,
+  on_status: Option<ThreadsafeFunction<ReconnectStatus, ErrorStrategy::Fatal>>,
+}
+
+impl Reconnector {
+  fn spawn(
+    url: String,
+    options: SocketOptions,
+    sink: MessageSink,
+    on_status: Option<ThreadsafeFunction<ReconnectStatus, ErrorStrategy::Fatal>>,
+  ) -> Result<MessageRecvDisposable> {
+    let heartbeat = HeartbeatConfig::from_options(&options)?;
+    let (tx, rx) = mpsc::channel::<()>();
+    let connection_alive = Arc::new(AtomicBool::new(false));
+    let disposable = MessageRecvDisposable::new(
+      vec![tx],
+      connection_alive.clone(),
+      Socket::create_client(&options)?,
+      options.protocol(),
+      sink.recv_queue(),
+    );
+    let reconnector = Reconnector {
+      url,
+      policy: options.reconnect.clone().unwrap_or_default(),
+      options,
+      heartbeat,
+      client: disposable.client.clone(),
+      topics: disposable.topics.clone(),
+      connection_alive,
+      last_seen: Arc::new(Mutex::new(Instant::now())),
+      control: disposable.control.clone(),
+      sink,
+      on_status,
+    };
+    thread::spawn(move || reconnector.run(rx));
+    Ok(disposable)
+  }
+
+  fn run(self, rx: mpsc::Receiver<()>) {
+    let mut attempt = 0;
+    self.report(ReconnectState::Connecting, attempt, None, None);
+    loop {
+      let client = self.client.lock().unwrap().clone();
+      let error = match self
+        .watch_peer(&client)
+        .and_then(|_| self.options.start_dialer(&client, &self.url, false))
+      {
+        Ok(()) => {
+          self.connection_alive.store(true, Ordering::Relaxed);
+          *self.last_seen.lock().unwrap() = Instant::now();
+          self.report(ReconnectState::Connected, attempt, None, None);
+          attempt = 0;
+          match self.run_session(&client, &rx) {
+            RecvExit::Stopped => {
+              client.close();
+              return;
+            }
+            RecvExit::Lost(e) => {
+              // 断开后的 Socket 不再复用，换一个新的并恢复订阅；
+              // 新的 Socket 创建失败时算作第一次重连失败
+              client.close();
+              if let Err(e) = self.renew_client() {
+                return self.give_up(attempt + 1, e.reason);
+              }
+              format!("Connection lost: {}", e)
+            }
+          }
+        }
+        Err(e) => format!("Failed to connect: {}", e.reason),
+      };
+
+      if self.policy.max_attempts.is_some_and(|max| attempt >= max) {
+        return self.give_up(attempt, error);
+      }
+      attempt += 1;
+      let delay = self.policy.delay(attempt);
+      self.report(
+        ReconnectState::Reconnecting,
+        attempt,
+        Some(delay),
+        Some(error),
+      );
+      // 等待期间也要响应 dispose
+      if !matches!(rx.recv_timeout(delay), Err(RecvTimeoutError::Timeout)) {
+        self.client.lock().unwrap().close();
+        return;
+      }
+    }
+  }
+
+  fn run_session(&self, client: &nng::Socket, rx: &mpsc::Receiver<()>) -> RecvExit {
+    // 心跳线程在会话结束时随 _heartbeat_tx 一起退出，心跳超时时关闭 Socket 触发重连
+    let _heartbeat_tx = self
+      .heartbeat
+      .clone()
+      .filter(|h| h.enabled)
+      .map(|heartbeat| {
+        let (tx, rx) = mpsc::channel::<()>();
+        spawn_heartbeat(
+          client.clone(),
+          heartbeat,
+          rx,
+          self.sink.clone(),
+          self.connection_alive.clone(),
+          self.last_seen.clone(),
+          true,
+        );
+        tx
+      });
+    let exit = recv_session(
+      client,
+      self.heartbeat.as_ref(),
+      rx,
+      &self.control,
+      &self.sink,
+      &self.connection_alive,
+      &self.last_seen,
+      &self.options,
+    );
+    self.connection_alive.store(false, Ordering::Relaxed);
+    exit
+  }
+
+  // 连接断开时 nng 触发 RemovePost，不依赖心跳或接收出错就能发现对端已经断开。
+  // 每次连接前重新登记，旧 Socket 上迟到的通知属于已经结束的会话
+  fn watch_peer(&self, client: &nng::Socket) -> Result<()> {
+    let control = self.control.clone();
+    let session = control.new_session();
+    client
+      .pipe_notify(move |_, event| {
+        if event == nng::PipeEvent::RemovePost {
+          control.lose_peer(session);
+        }
+      })
+      .map_err(|e| Error::from_reason(format!("Register pipe notify failed: {}", e)))
+  }
+
+  fn renew_client(&self) -> Result<()> {
+    let mut client = self.client.lock().unwrap();
+    let renewed = Socket::create_client(&self.options)?;
+    for topic in self.topics.lock().unwrap().iter() {
+      subscribe(self.options.protocol(), &renewed, topic)?;
+    }
+    *client = renewed;
+    Ok(())
+  }
+
+  fn give_up(&self, attempt: u32, error: String) {
+    self.client.lock().unwrap().close();
+    self.report(ReconnectState::GaveUp, attempt, None, Some(error.clone()));
+    self.sink.error(Error::new(
       Status::GenericFailure,
-      format!("Compression failed: {}", e),
-    )),
+      format!("Reconnect gave up after {} attempts: {}", attempt, error),
+    ));
   }
+
+  fn report(
+    &self,
+    state: ReconnectState,
+    attempt: u32,
+    delay: Option<Duration>,
+    error: Option<String>,
+  ) {
+    if let Some(on_status) = &self.on_status {
+      on_status.call(
+        ReconnectStatus {
+          state,
+          attempt,
+          delay: delay.map(|d| d.as_millis().try_into().unwrap_or(u32::MAX)),
+          error,
+        },
+        ThreadsafeFunctionCallMode::NonBlocking,
+      );
+    }
+  }
+}
+
+// 心跳线程：按间隔发送 ping，并在连续 max_missed 个间隔没有收到任何消息时标记连接断开，
+// close_on_timeout 时顺带关闭 Socket 让接收线程重连
+fn spawn_heartbeat(
+  client: nng::Socket,
+  heartbeat: HeartbeatConfig,
+  rx: mpsc::Receiver<()>,
+  sink: MessageSink,
+  connection_alive: Arc<AtomicBool>,
+  last_seen: Arc<Mutex<Instant>>,
+  close_on_timeout: bool,
+) {
+  let deadline = heartbeat.interval * heartbeat.max_missed;
+  thread::spawn(move || loop {
+    match rx.recv_timeout(heartbeat.interval) {
+      Err(RecvTimeoutError::Timeout) => {}
+      _ => return,
+    }
+
+    let ping = nng::Message::from(&heartbeat.ping_wire[..]);
+    if let Err((_, nng::Error::Closed)) = client.try_send(ping) {
+      return;
+    }
+
+    if sink.is_stalled() {
+      *last_seen.lock().unwrap() = Instant::now();
+      continue;
+    }
+    let elapsed = last_seen.lock().unwrap().elapsed();
+    // 只在存活 -> 断开的状态切换时通知一次，收到新消息后接收线程会恢复存活状态
+    if elapsed > deadline && connection_alive.swap(false, Ordering::Relaxed) {
+      let delivered = sink.error(Error::new(
+        Status::GenericFailure,
+        format!(
+          "Heartbeat timeout: no message from peer in {}ms",
+          elapsed.as_millis()
+        ),
+      ));
+      if close_on_timeout {
+        client.close();
+        return;
+      }
+      if !delivered {
+        return;
+      }
+    }
+  });
+}
+
+fn topic_bytes(topic: Either<Buffer, String>) -> Vec<u8> {
+  match topic {
+    Either::A(buf) => buf.to_vec(),
+    Either::B(text) => text.into_bytes(),
+  }
+}
+
+fn subscribe(protocol: SocketProtocol, client: &nng::Socket, topic: &[u8]) -> Result<()> {
+  protocol.ensure("subscribe", &[SocketProtocol::Sub])?;
+  client
+    .set_opt::<Subscribe>(topic.to_vec())
+    .map_err(|e| Error::from_reason(format!("Subscribe failed: {}", e)))
+}
+
+fn unsubscribe(protocol: SocketProtocol, client: &nng::Socket, topic: &[u8]) -> Result<()> {
+  protocol.ensure("unsubscribe", &[SocketProtocol::Sub])?;
+  client
+    .set_opt::<Unsubscribe>(topic.to_vec())
+    .map_err(|e| Error::from_reason(format!("Unsubscribe failed: {}", e)))
 }
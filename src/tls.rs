@@ -0,0 +1,316 @@
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum TlsAuthMode {
+  // 不校验对端证书
+  None,
+  // 对端提供了证书时校验，没有提供时也允许连接
+  Optional,
+  // 对端必须提供有效的证书
+  Required,
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+pub struct TlsOptions {
+  /// 用于校验对端证书的 CA 证书（PEM），可以拼接多个证书。nng 不使用系统的受信任证书，connect 校验服务端证书时必须设置
+  pub ca: Option<String>,
+  /// 本端证书或证书链（PEM），listen 时必须设置，connect 时只在服务端要求客户端证书时需要
+  pub cert: Option<String>,
+  /// 本端证书的私钥（PEM）
+  pub key: Option<String>,
+  /// 私钥的密码，私钥没有加密时不需要
+  pub passphrase: Option<String>,
+  /// connect 时与服务端证书匹配的名称，默认取连接地址中的主机名
+  pub server_name: Option<String>,
+  /// 对端证书的校验方式，connect 默认 required，listen 默认 none
+  pub auth_mode: Option<TlsAuthMode>,
+}
+
+// tls+tcp:// 和 wss:// 地址需要 TLS 配置
+pub(crate) fn is_tls_url(url: &str) -> bool {
+  url
+    .split_once("://")
+    .is_some_and(|(scheme, _)| scheme.starts_with("tls+") || scheme.starts_with("wss"))
+}
+
+// TLS 握手失败时 nng 只返回笼统的错误码，补充最常见的原因
+pub(crate) fn describe_error(e: nng::Error, url: &str) -> String {
+  if !is_tls_url(url) {
+    return e.to_string();
+  }
+  let hint = match e {
+    nng::Error::PeerAuth => {
+      "TLS handshake failed: the peer certificate was rejected, check ca, serverName and authMode"
+    }
+    nng::Error::Crypto => {
+      "TLS handshake failed: check that both sides use TLS and that the cert matches the key"
+    }
+    nng::Error::ConnectionShutdown | nng::Error::ConnectionReset => {
+      "the peer closed the connection during the TLS handshake, it may require a client certificate"
+    }
+    _ => return e.to_string(),
+  };
+  format!("{} ({})", e, hint)
+}
+
+#[cfg(feature = "tls")]
+pub(crate) fn configure_dialer(
+  options: Option<&TlsOptions>,
+  dialer: &nng::DialerBuilder,
+  url: &str,
+) -> Result<()> {
+  // nng 的 TLS 基于 mbedTLS，不会读取系统的受信任证书：默认的 required 校验没有 ca 时每次握手都会失败，
+  // 这里直接报错，而不是等到握手时报笼统的 PeerAuth
+  let default_options = TlsOptions::default();
+  let config = TlsConfig::new(options.unwrap_or(&default_options), false, url)?;
+  // SAFETY: nng 在设置时持有配置的引用，TlsConfig 释放的只是我们自己的引用
+  let rv = unsafe {
+    nng_sys::nng_dialer_set_ptr(
+      dialer.nng_dialer(),
+      nng_sys::NNG_OPT_TLS_CONFIG.as_ptr() as _,
+      config.0.cast(),
+    )
+  };
+  check(rv, "Set TLS config failed")
+}
+
+#[cfg(feature = "tls")]
+pub(crate) fn configure_listener(
+  options: Option<&TlsOptions>,
+  listener: &nng::ListenerBuilder,
+  url: &str,
+) -> Result<()> {
+  let options = options.ok_or_else(|| {
+    Error::new(
+      Status::InvalidArg,
+      format!("Listen {} requires tls options with cert and key", url),
+    )
+  })?;
+  let config = TlsConfig::new(options, true, url)?;
+  // SAFETY: 同 configure_dialer
+  let rv = unsafe {
+    nng_sys::nng_listener_set_ptr(
+      listener.nng_listener(),
+      nng_sys::NNG_OPT_TLS_CONFIG.as_ptr() as _,
+      config.0.cast(),
+    )
+  };
+  check(rv, "Set TLS config failed")
+}
+
+#[cfg(not(feature = "tls"))]
+pub(crate) fn configure_dialer(
+  _: Option<&TlsOptions>,
+  _: &nng::DialerBuilder,
+  url: &str,
+) -> Result<()> {
+  Err(not_enabled(url))
+}
+
+#[cfg(not(feature = "tls"))]
+pub(crate) fn configure_listener(
+  _: Option<&TlsOptions>,
+  _: &nng::ListenerBuilder,
+  url: &str,
+) -> Result<()> {
+  Err(not_enabled(url))
+}
+
+#[cfg(not(feature = "tls"))]
+fn not_enabled(url: &str) -> Error {
+  Error::new(
+    Status::GenericFailure,
+    format!(
+      "{} requires TLS support, which is not enabled in this build (rebuild with --features tls)",
+      url
+    ),
+  )
+}
+
+#[cfg(feature = "tls")]
+fn check(rv: std::os::raw::c_int, context: &str) -> Result<()> {
+  match std::num::NonZeroU32::new(rv as u32) {
+    None => Ok(()),
+    Some(code) => Err(Error::new(
+      Status::InvalidArg,
+      format!("{}: {}", context, nng::Error::from(code)),
+    )),
+  }
+}
+
+// 连接地址中的主机名，用作默认的 serverName
+#[cfg(feature = "tls")]
+fn url_host(url: &str) -> Option<&str> {
+  let (_, rest) = url.split_once("://")?;
+  let authority = rest.split('/').next()?;
+  let host = match authority.strip_prefix('[') {
+    Some(ipv6) => ipv6.split(']').next()?,
+    None => authority
+      .rsplit_once(':')
+      .map_or(authority, |(host, _)| host),
+  };
+  (!host.is_empty() && host != "*").then_some(host)
+}
+
+// nng_tls_config 的所有权，释放时减少引用计数
+#[cfg(feature = "tls")]
+struct TlsConfig(*mut ffi::nng_tls_config);
+
+#[cfg(feature = "tls")]
+impl TlsConfig {
+  fn new(options: &TlsOptions, server: bool, url: &str) -> Result<Self> {
+    let mode = if server {
+      ffi::NNG_TLS_MODE_SERVER
+    } else {
+      ffi::NNG_TLS_MODE_CLIENT
+    };
+    let mut raw = std::ptr::null_mut();
+    // SAFETY: 分配成功后由 TlsConfig 负责释放
+    check(
+      unsafe { ffi::nng_tls_config_alloc(&mut raw, mode) },
+      "Create TLS config failed",
+    )?;
+    let config = TlsConfig(raw);
+
+    if let Some(ca) = &options.ca {
+      let ca = c_string(ca, "ca")?;
+      check(
+        // SAFETY: raw 是刚分配的有效配置，ca 是以 NUL 结尾的字符串，nng 会复制内容；
+        // 不使用吊销列表，传空指针
+        unsafe { ffi::nng_tls_config_ca_chain(raw, ca.as_ptr(), std::ptr::null()) },
+        "Invalid TLS ca",
+      )?;
+    }
+    match (&options.cert, &options.key) {
+      (Some(cert), Some(key)) => {
+        let cert = c_string(cert, "cert")?;
+        let key = c_string(key, "key")?;
+        let passphrase = options
+          .passphrase
+          .as_deref()
+          .map(|p| c_string(p, "passphrase"))
+          .transpose()?;
+        check(
+          // SAFETY: raw 有效，cert / key / passphrase 都是以 NUL 结尾的字符串并在调用期间存活，
+          // nng 会复制内容；私钥没有加密时 passphrase 传空指针
+          unsafe {
+            ffi::nng_tls_config_own_cert(
+              raw,
+              cert.as_ptr(),
+              key.as_ptr(),
+              passphrase.as_ref().map_or(std::ptr::null(), |p| p.as_ptr()),
+            )
+          },
+          "Invalid TLS cert or key",
+        )?;
+      }
+      (None, None) if server => {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!("Listen {} requires tls options with cert and key", url),
+        ))
+      }
+      (None, None) => {}
+      _ => {
+        return Err(Error::new(
+          Status::InvalidArg,
+          "TLS cert and key must be set together".to_string(),
+        ))
+      }
+    }
+    if !server {
+      let verifies = !matches!(
+        options.auth_mode,
+        Some(TlsAuthMode::None) | Some(TlsAuthMode::Optional)
+      );
+      if verifies && options.ca.is_none() {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!(
+            "Connect {} requires tls.ca to verify the server certificate (there is no system trust store), or set tls.authMode to \"none\" to skip verification",
+            url
+          ),
+        ));
+      }
+      if let Some(name) = options.server_name.as_deref().or_else(|| url_host(url)) {
+        let name = c_string(name, "serverName")?;
+        check(
+          // SAFETY: raw 有效，name 是以 NUL 结尾的字符串，nng 会复制内容
+          unsafe { ffi::nng_tls_config_server_name(raw, name.as_ptr()) },
+          "Invalid TLS serverName",
+        )?;
+      }
+    }
+    if let Some(auth_mode) = &options.auth_mode {
+      let auth_mode = match auth_mode {
+        TlsAuthMode::None => ffi::NNG_TLS_AUTH_MODE_NONE,
+        TlsAuthMode::Optional => ffi::NNG_TLS_AUTH_MODE_OPTIONAL,
+        TlsAuthMode::Required => ffi::NNG_TLS_AUTH_MODE_REQUIRED,
+      };
+      check(
+        // SAFETY: raw 有效，auth_mode 是 nng 定义的枚举值之一
+        unsafe { ffi::nng_tls_config_auth_mode(raw, auth_mode) },
+        "Invalid TLS authMode",
+      )?;
+    }
+    Ok(config)
+  }
+}
+
+#[cfg(feature = "tls")]
+impl Drop for TlsConfig {
+  fn drop(&mut self) {
+    // SAFETY: self.0 由 nng_tls_config_alloc 分配，只释放一次
+    unsafe { ffi::nng_tls_config_free(self.0) }
+  }
+}
+
+#[cfg(feature = "tls")]
+fn c_string(value: &str, name: &str) -> Result<std::ffi::CString> {
+  std::ffi::CString::new(value).map_err(|_| {
+    Error::new(
+      Status::InvalidArg,
+      format!("TLS {} must not contain NUL characters", name),
+    )
+  })
+}
+
+// nng-sys 预生成的绑定不包含 supplemental/tls/tls.h，按头文件声明用到的部分
+#[cfg(feature = "tls")]
+#[allow(non_camel_case_types)]
+mod ffi {
+  use std::os::raw::{c_char, c_int};
+
+  #[repr(C)]
+  pub struct nng_tls_config {
+    _private: [u8; 0],
+  }
+
+  pub const NNG_TLS_MODE_CLIENT: c_int = 0;
+  pub const NNG_TLS_MODE_SERVER: c_int = 1;
+
+  pub const NNG_TLS_AUTH_MODE_NONE: c_int = 0;
+  pub const NNG_TLS_AUTH_MODE_OPTIONAL: c_int = 1;
+  pub const NNG_TLS_AUTH_MODE_REQUIRED: c_int = 2;
+
+  extern "C" {
+    pub fn nng_tls_config_alloc(config: *mut *mut nng_tls_config, mode: c_int) -> c_int;
+    pub fn nng_tls_config_free(config: *mut nng_tls_config);
+    pub fn nng_tls_config_server_name(config: *mut nng_tls_config, name: *const c_char) -> c_int;
+    pub fn nng_tls_config_ca_chain(
+      config: *mut nng_tls_config,
+      chain: *const c_char,
+      crl: *const c_char,
+    ) -> c_int;
+    pub fn nng_tls_config_own_cert(
+      config: *mut nng_tls_config,
+      cert: *const c_char,
+      key: *const c_char,
+      pass: *const c_char,
+    ) -> c_int;
+    pub fn nng_tls_config_auth_mode(config: *mut nng_tls_config, mode: c_int) -> c_int;
+  }
+}
@@ -0,0 +1,155 @@
+use std::{collections::HashMap, ffi::CStr, os::raw::c_int};
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use nng::options::{
+  transport::websocket::{RequestHeaders, ResponseHeaders},
+  Options,
+};
+
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum WebSocketMessageType {
+  Binary,
+  // 文本帧，消息内容需要是合法的 UTF-8
+  Text,
+}
+
+#[napi(object)]
+#[derive(Clone, Debug, Default)]
+pub struct WebSocketOptions {
+  /// connect 时随握手请求发送的 HTTP 头，例如 Authorization
+  pub request_headers: Option<HashMap<String, String>>,
+  /// listen 时随握手响应返回的 HTTP 头
+  pub response_headers: Option<HashMap<String, String>>,
+  /// 替换地址中的路径，例如 /nng；同一个端口上的多个 listener 按路径区分
+  pub path: Option<String>,
+  /// 发送和接收使用的帧类型，默认 binary
+  pub message_type: Option<WebSocketMessageType>,
+}
+
+// ws:// 和 wss://（包括 ws4 / ws6 等）地址
+pub(crate) fn is_websocket_url(url: &str) -> bool {
+  url
+    .split_once("://")
+    .is_some_and(|(scheme, _)| scheme.starts_with("ws"))
+}
+
+impl WebSocketOptions {
+  // 设置了 path 时替换地址中的路径部分
+  pub(crate) fn url(&self, url: &str) -> String {
+    let (Some(path), Some((scheme, rest))) = (&self.path, url.split_once("://")) else {
+      return url.to_string();
+    };
+    let authority = rest.split('/').next().unwrap_or(rest);
+    format!(
+      "{}://{}/{}",
+      scheme,
+      authority,
+      path.trim_start_matches('/')
+    )
+  }
+
+  pub(crate) fn configure_dialer(&self, dialer: &nng::DialerBuilder) -> Result<()> {
+    if let Some(headers) = &self.request_headers {
+      dialer
+        .set_opt::<RequestHeaders>(header_lines(headers)?)
+        .map_err(|e| option_error("requestHeaders", e))?;
+    }
+    if self.message_type == Some(WebSocketMessageType::Text) {
+      // SAFETY: 选项名是 nng_sys 中以 \0 结尾的常量
+      set_text_mode(|option| unsafe {
+        nng_sys::nng_dialer_set_bool(dialer.nng_dialer(), option.as_ptr() as _, true)
+      })?;
+    }
+    Ok(())
+  }
+
+  pub(crate) fn configure_listener(&self, listener: &nng::ListenerBuilder) -> Result<()> {
+    if let Some(headers) = &self.response_headers {
+      listener
+        .set_opt::<ResponseHeaders>(header_lines(headers)?)
+        .map_err(|e| option_error("responseHeaders", e))?;
+    }
+    if self.message_type == Some(WebSocketMessageType::Text) {
+      // SAFETY: 同 configure_dialer
+      set_text_mode(|option| unsafe {
+        nng_sys::nng_listener_set_bool(listener.nng_listener(), option.as_ptr() as _, true)
+      })?;
+    }
+    Ok(())
+  }
+}
+
+// WebSocket 连接的握手请求，listen 端可以据此识别客户端（例如 Cookie、Authorization）
+#[derive(Debug, Default)]
+pub(crate) struct WebSocketRequest {
+  pub uri: Option<String>,
+  pub headers: Option<HashMap<String, String>>,
+}
+
+impl WebSocketRequest {
+  // 其他传输方式的连接读取选项会失败，两项都为 None
+  pub(crate) fn of(pipe: nng::Pipe) -> Self {
+    WebSocketRequest {
+      uri: pipe_string(pipe, nng_sys::NNG_OPT_WS_REQUEST_URI),
+      headers: pipe
+        .get_opt::<RequestHeaders>()
+        .ok()
+        .map(|headers| parse_headers(&headers)),
+    }
+  }
+}
+
+// nng 的 HTTP 头格式：每行 "Name: value\r\n"
+fn header_lines(headers: &HashMap<String, String>) -> Result<String> {
+  let mut lines = String::new();
+  for (name, value) in headers {
+    let invalid = |s: &str| s.contains(['\r', '\n']);
+    if name.is_empty() || name.contains(':') || invalid(name) || invalid(value) {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("Invalid WebSocket header {:?}", name),
+      ));
+    }
+    lines.push_str(&format!("{}: {}\r\n", name, value));
+  }
+  Ok(lines)
+}
+
+fn parse_headers(lines: &str) -> HashMap<String, String> {
+  lines
+    .split("\r\n")
+    .filter_map(|line| line.split_once(':'))
+    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+    .collect()
+}
+
+fn set_text_mode(set: impl Fn(&[u8]) -> c_int) -> Result<()> {
+  for option in [nng_sys::NNG_OPT_WS_SEND_TEXT, nng_sys::NNG_OPT_WS_RECV_TEXT] {
+    if let Some(code) = std::num::NonZeroU32::new(set(option) as u32) {
+      return Err(option_error("messageType", nng::Error::from(code)));
+    }
+  }
+  Ok(())
+}
+
+fn option_error(option: &str, e: nng::Error) -> Error {
+  Error::new(
+    Status::InvalidArg,
+    format!("Set WebSocket {} failed: {}", option, e),
+  )
+}
+
+fn pipe_string(pipe: nng::Pipe, option: &[u8]) -> Option<String> {
+  let mut value = std::ptr::null_mut();
+  // SAFETY: 成功时 nng 分配一个以 \0 结尾的字符串，复制后用 nng_strfree 释放
+  unsafe {
+    if nng_sys::nng_pipe_get_string(pipe.nng_pipe(), option.as_ptr() as _, &mut value) != 0 {
+      return None;
+    }
+    let result = CStr::from_ptr(value).to_string_lossy().into_owned();
+    nng_sys::nng_strfree(value);
+    Some(result)
+  }
+}
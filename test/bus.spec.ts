@@ -0,0 +1,30 @@
+import { Socket, SocketProtocol } from "../nng";
+
+describe("bus", () => {
+  it("delivers messages to every directly connected peer", async () => {
+    const hub = new Socket({ protocol: SocketProtocol.Bus });
+    hub.listen("inproc://bus-spec");
+
+    const peers = [0, 1].map(() => {
+      const peer = new Socket({ protocol: SocketProtocol.Bus });
+      peer.connect("inproc://bus-spec");
+      return peer;
+    });
+
+    const received = peers.map(
+      (peer) =>
+        new Promise<string>((resolve) => {
+          const disposable = peer.onMessage((err, data) => {
+            if (err) return;
+            disposable.dispose();
+            resolve(data.toString());
+          });
+        })
+    );
+    hub.sendOnly(Buffer.from("state"));
+
+    expect(await Promise.all(received)).toEqual(["state", "state"]);
+    peers.forEach((peer) => peer.close());
+    hub.close();
+  });
+});
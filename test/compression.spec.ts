@@ -0,0 +1,90 @@
+import { CompressionAlgorithm, Socket, SocketProtocol } from "../nng";
+
+const payload = Buffer.from(JSON.stringify({ items: Array(200).fill({ id: 1, name: "nng" }) }));
+
+describe("transparent compression", () => {
+  it("compresses requests and replies on req/rep", async () => {
+    const url = "inproc://compression-reqrep";
+    const compression = { algorithm: CompressionAlgorithm.Zstd };
+    const server = new Socket({ protocol: SocketProtocol.Rep, compression });
+    server.listen(url);
+    const client = new Socket({ protocol: SocketProtocol.Req, recvTimeout: 2000, compression });
+    client.connect(url);
+
+    const disposable = server.serve((err, req) => Buffer.concat([req, Buffer.from("!")]));
+    const reply = await client.sendAsync(payload);
+    expect(reply.toString()).toBe(`${payload.toString()}!`);
+
+    disposable.dispose();
+    client.close();
+    server.close();
+  });
+
+  it("lets compressed and uncompressed messages share one connection", async () => {
+    const url = "inproc://compression-mixed";
+    const pull = new Socket({
+      protocol: SocketProtocol.Pull,
+      compression: { algorithm: CompressionAlgorithm.None },
+    });
+    pull.listen(url);
+    const push = new Socket({
+      protocol: SocketProtocol.Push,
+      compression: { algorithm: CompressionAlgorithm.Lz4, minSize: 64 },
+    });
+    push.connect(url);
+
+    push.sendOnly(Buffer.from("small"));
+    push.sendOnly(payload);
+    expect((await pull.recvAsync()).toString()).toBe("small");
+    expect((await pull.recvAsync()).equals(payload)).toBe(true);
+
+    push.close();
+    pull.close();
+  });
+
+  it("marks only compressed messages", async () => {
+    const url = "inproc://compression-marker";
+    const pull = new Socket({ protocol: SocketProtocol.Pull });
+    pull.listen(url);
+    const push = new Socket({ protocol: SocketProtocol.Push, compression: {} });
+    push.connect(url);
+
+    push.sendOnly(Buffer.from("small"));
+    push.sendOnly(payload);
+    expect((await pull.recvAsync()).toString()).toBe("small");
+    const compressed = await pull.recvAsync();
+    expect([...compressed.subarray(0, 5)]).toEqual([0xfe, 0x4e, 0x4e, 0x5a, 1]);
+    expect(compressed.length).toBeLessThan(payload.length);
+
+    push.close();
+    pull.close();
+  });
+
+  it("passes through messages from a peer without compression", async () => {
+    const url = "inproc://compression-passthrough";
+    const pull = new Socket({ protocol: SocketProtocol.Pull, compression: {} });
+    pull.listen(url);
+    const plain = new Socket({ protocol: SocketProtocol.Push });
+    plain.connect(url);
+    const compressing = new Socket({ protocol: SocketProtocol.Push, compression: {} });
+    compressing.connect(url);
+
+    // 以前的 1 字节压缩头（0 / 1 / 2）对没有标记的消息不再有特殊含义
+    plain.sendOnly(Buffer.from([1, 2, 3]));
+    expect([...(await pull.recvAsync())]).toEqual([1, 2, 3]);
+    // 原始内容恰好以标记开头时，发送方会把它标记为未压缩
+    const lookalike = Buffer.from([0xfe, 0x4e, 0x4e, 0x5a, 2, 9]);
+    compressing.sendOnly(lookalike);
+    expect((await pull.recvAsync()).equals(lookalike)).toBe(true);
+
+    plain.close();
+    compressing.close();
+    pull.close();
+  });
+
+  it("is not supported on pub/sub", () => {
+    expect(() => new Socket({ protocol: SocketProtocol.Sub, compression: {} })).toThrow(
+      "compression is not supported on a sub socket"
+    );
+  });
+});
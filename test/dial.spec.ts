@@ -0,0 +1,32 @@
+import { Socket } from "../nng";
+
+describe("dial", () => {
+  it("fails immediately when the peer is not up by default", () => {
+    const client = new Socket();
+    expect(() => client.connect("inproc://dial-blocking")).toThrow(
+      "Connect inproc://dial-blocking failed"
+    );
+    client.close();
+  });
+
+  it("lets the client start before the server with nonBlockingDial", () => {
+    const url = "inproc://dial-non-blocking";
+    const client = new Socket({
+      nonBlockingDial: true,
+      reconnectMinTime: 10,
+      reconnectMaxTime: 50,
+    });
+    client.connect(url);
+    expect(client.connected()).toBe(false);
+
+    const server = new Socket();
+    server.listen(url);
+
+    client.sendOnly(Buffer.from("late"));
+    expect(server.recv().toString()).toBe("late");
+    expect(client.connected()).toBe(true);
+
+    client.close();
+    server.close();
+  });
+});
@@ -0,0 +1,120 @@
+import { QueuePolicy, Socket, SocketOptions, SocketProtocol } from "../nng";
+
+const wait = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+// 阻塞 JS 线程，让回调来不及处理
+const busy = (ms: number) => {
+  const end = Date.now() + ms;
+  while (Date.now() < end) {}
+};
+
+describe("receive flow control", () => {
+  let seq = 0;
+
+  const setup = (options: SocketOptions) => {
+    const url = `inproc://flow-control-${seq++}`;
+    const pull = new Socket({ protocol: SocketProtocol.Pull, ...options });
+    pull.listen(url);
+    const push = new Socket({ protocol: SocketProtocol.Push });
+    push.connect(url);
+    const received: string[] = [];
+    const errors: string[] = [];
+    const disposable = pull.onMessage((err, data) => {
+      if (err) errors.push(err.message);
+      else received.push(data.toString());
+    });
+    const sendBurst = () => {
+      for (let i = 0; i < 10; i++) push.sendOnly(Buffer.from(String(i)));
+      busy(200);
+    };
+    const close = () => {
+      disposable.dispose();
+      push.close();
+      pull.close();
+    };
+    return { pull, push, disposable, received, errors, sendBurst, close };
+  };
+
+  it("keeps every message with the block policy", async () => {
+    const { disposable, received, sendBurst, close } = setup({ maxQueueSize: 2 });
+    sendBurst();
+    await wait(100);
+    expect(received).toEqual(["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+    expect(disposable.droppedCount()).toBe(0);
+    close();
+  });
+
+  it("drops the newest messages", async () => {
+    const { disposable, received, sendBurst, close } = setup({
+      maxQueueSize: 2,
+      queuePolicy: QueuePolicy.DropNewest,
+    });
+    sendBurst();
+    await wait(100);
+    expect(received).toEqual(["0", "1"]);
+    expect(disposable.droppedCount()).toBe(8);
+    close();
+  });
+
+  it("drops the oldest messages", async () => {
+    const { disposable, received, sendBurst, close } = setup({
+      maxQueueSize: 2,
+      queuePolicy: QueuePolicy.DropOldest,
+    });
+    sendBurst();
+    await wait(100);
+    expect(received).toEqual(["8", "9"]);
+    expect(disposable.droppedCount()).toBe(8);
+    close();
+  });
+
+  it("reports an error and stops with the error policy", async () => {
+    const { disposable, received, errors, sendBurst, close } = setup({
+      maxQueueSize: 2,
+      queuePolicy: QueuePolicy.Error,
+    });
+    sendBurst();
+    await wait(100);
+    expect(received).toEqual(["0", "1"]);
+    expect(errors).toEqual(["Receive queue full: more than 2 messages pending"]);
+    expect(disposable.isConnectionAlive()).toBe(false);
+    close();
+  });
+
+  it("pauses and resumes receiving", async () => {
+    const { push, disposable, received, close } = setup({});
+    push.sendOnly(Buffer.from("before"));
+    await wait(50);
+    disposable.pause();
+    // 暂停前已经在等待的那次接收仍可能取到一条
+    ["a", "b", "c"].forEach((m) => push.sendOnly(Buffer.from(m)));
+    await wait(100);
+    expect(received.length).toBeLessThanOrEqual(2);
+    disposable.resume();
+    await wait(100);
+    expect(received).toEqual(["before", "a", "b", "c"]);
+    close();
+  });
+
+  it("neither loses nor delivers messages around dispose", async () => {
+    const { pull, push, disposable, received, close } = setup({});
+    push.sendOnly(Buffer.from("before"));
+    disposable.dispose();
+    push.sendOnly(Buffer.from("after"));
+    await wait(100);
+    // dispose 前已经取出的消息交给回调，其余的留在 Socket 中
+    const leftovers: string[] = [];
+    for (let data = pull.tryRecv(); data; data = pull.tryRecv()) leftovers.push(data.toString());
+    expect(received).not.toContain("after");
+    expect([...received, ...leftovers]).toEqual(["before", "after"]);
+    close();
+  });
+
+  it("is not available on serve", () => {
+    const server = new Socket({ protocol: SocketProtocol.Rep });
+    const disposable = server.serve((err, req) => req);
+    expect(() => disposable.pause()).toThrow("pause is only supported by recvMessage / onMessage");
+    disposable.dispose();
+    server.close();
+  });
+});
@@ -0,0 +1,60 @@
+import { Socket, SocketProtocol } from "../nng";
+
+describe("heartbeat", () => {
+  it("keeps the connection alive while the peer answers pings", async () => {
+    const server = new Socket({ heartbeatAutoReply: true });
+    server.listen("inproc://heartbeat-alive");
+    const serverMessages: string[] = [];
+    const serverDisposable = server.onMessage((err, data) => {
+      if (!err) serverMessages.push(data.toString());
+    });
+
+    const errors: Error[] = [];
+    const disposable = Socket.recvMessage(
+      "inproc://heartbeat-alive",
+      (err) => {
+        if (err) errors.push(err);
+      },
+      { enableHeartbeat: true, heartbeatInterval: 50, heartbeatMaxMissed: 2 }
+    );
+
+    await new Promise((resolve) => setTimeout(resolve, 400));
+    expect(errors).toEqual([]);
+    expect(serverMessages).toEqual([]);
+    expect(disposable.isConnectionAlive()).toBe(true);
+
+    disposable.dispose();
+    serverDisposable.dispose();
+    server.close();
+  });
+
+  it("reports a timeout when the peer stops answering", async () => {
+    const server = new Socket();
+    server.listen("inproc://heartbeat-timeout");
+
+    const timedOut = new Promise<Error>((resolve) => {
+      Socket.recvMessage(
+        "inproc://heartbeat-timeout",
+        (err) => {
+          if (err) resolve(err);
+        },
+        { enableHeartbeat: true, heartbeatInterval: 50, heartbeatMaxMissed: 2 }
+      );
+    });
+
+    expect((await timedOut).message).toContain("Heartbeat timeout");
+    server.close();
+  });
+
+  it("rejects heartbeat on one-way protocols", () => {
+    const pub = new Socket({ protocol: SocketProtocol.Pub });
+    pub.listen("inproc://heartbeat-sub");
+    expect(() =>
+      Socket.recvMessage("inproc://heartbeat-sub", () => {}, {
+        protocol: SocketProtocol.Sub,
+        enableHeartbeat: true,
+      })
+    ).toThrow("heartbeat is not supported on a sub socket");
+    pub.close();
+  });
+});
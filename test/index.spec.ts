@@ -1,4 +1,4 @@
-import { SocketWrapper } from "../index";
+import { SocketWrapper } from "../nng";
 
 describe("default", () => {
   let socket: SocketWrapper;
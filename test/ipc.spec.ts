@@ -0,0 +1,73 @@
+import { execFileSync } from "child_process";
+import { existsSync, mkdtempSync, statSync, writeFileSync } from "fs";
+import { tmpdir } from "os";
+import { join } from "path";
+import { PipeEventInfo, Socket, SocketProtocol } from "../nng";
+
+const unix = process.platform !== "win32";
+const dir = unix ? mkdtempSync(join(tmpdir(), "nng-ipc-")) : "";
+
+describe.runIf(unix)("ipc", () => {
+  it("applies socket file permissions and reports peer credentials", async () => {
+    const path = join(dir, "perm.sock");
+    const server = new Socket({ protocol: SocketProtocol.Rep, ipc: { permissions: 0o600 } });
+    const added = new Promise<PipeEventInfo>((resolve) => server.on("pipeAdded", resolve));
+    server.listen(`ipc://${path}`);
+    expect(statSync(path).mode & 0o777).toBe(0o600);
+
+    const client = new Socket({ protocol: SocketProtocol.Req });
+    client.connect(`ipc://${path}`);
+    const pipe = await added;
+    expect(pipe.peerUid).toBe(process.getuid!());
+    expect(pipe.peerGid).toBe(process.getgid!());
+    expect(pipe.peerPid).toBe(process.pid);
+
+    client.close();
+    server.close();
+  });
+
+  it("rejects connections from users that are not allowed", async () => {
+    const path = join(dir, "allow.sock");
+    const server = new Socket({
+      protocol: SocketProtocol.Pull,
+      ipc: { allowedUids: [process.getuid!() + 1] },
+    });
+    let added = 0;
+    server.on("pipeAdded", () => added++);
+    server.listen(`ipc://${path}`);
+
+    const client = new Socket({ protocol: SocketProtocol.Push, sendTimeout: 200 });
+    client.connect(`ipc://${path}`);
+    await new Promise((resolve) => setTimeout(resolve, 200));
+    expect(added).toBe(0);
+    expect(server.connected()).toBe(false);
+
+    client.close();
+    server.close();
+  });
+
+  it("removes stale socket files but keeps regular files and sockets in use", () => {
+    // 模拟进程异常退出：子进程监听后直接退出，socket 文件留在磁盘上
+    const path = join(dir, "stale.sock");
+    execFileSync(process.execPath, [
+      "-e",
+      `require("net").createServer().listen(${JSON.stringify(path)}, () => process.exit(0))`,
+    ]);
+    expect(existsSync(path)).toBe(true);
+    const server = new Socket({ protocol: SocketProtocol.Pair1 });
+    server.listen(`ipc://${path}`);
+
+    const second = new Socket({ protocol: SocketProtocol.Pair1 });
+    expect(() => second.listen(`ipc://${path}`)).toThrow("Listen");
+    expect(existsSync(path)).toBe(true);
+    second.close();
+    server.close();
+
+    const file = join(dir, "regular.sock");
+    writeFileSync(file, "not a socket");
+    const regular = new Socket({ protocol: SocketProtocol.Pair1 });
+    expect(() => regular.listen(`ipc://${file}`)).toThrow("Listen");
+    expect(existsSync(file)).toBe(true);
+    regular.close();
+  });
+});
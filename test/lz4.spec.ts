@@ -0,0 +1,93 @@
+import {
+  Lz4FrameDecoder,
+  Lz4FrameEncoder,
+  Lz4Mode,
+  lz4Compress,
+  lz4CompressAsync,
+  lz4CompressBlock,
+  lz4CompressBlockAsync,
+  lz4CompressBound,
+  lz4Decompress,
+  lz4DecompressBlock,
+  lz4FrameCompress,
+  lz4FrameDecompress,
+} from "../nng";
+
+const data = Buffer.from("nng ".repeat(10000) + "end");
+
+describe("lz4", () => {
+  it("round-trips raw blocks with and without maxSize", () => {
+    const compressed = lz4Compress(data);
+    expect(compressed.length).toBeLessThan(data.length);
+    expect(lz4Decompress(compressed, data.length).equals(data)).toBe(true);
+    expect(lz4Decompress(compressed).equals(data)).toBe(true);
+  });
+
+  it("supports fast and high compression modes", () => {
+    const fast = lz4Compress(data, { mode: Lz4Mode.Fast, acceleration: 8 });
+    const high = lz4Compress(data, { mode: Lz4Mode.HighCompression, level: 12 });
+    expect(high.length).toBeLessThanOrEqual(lz4Compress(data).length);
+    expect(lz4Decompress(fast, data.length).equals(data)).toBe(true);
+    expect(lz4Decompress(high, data.length).equals(data)).toBe(true);
+  });
+
+  it("compresses on the thread pool", async () => {
+    const compressed = await lz4CompressAsync(data, { mode: Lz4Mode.HighCompression });
+    expect(lz4Decompress(compressed, data.length).equals(data)).toBe(true);
+    const packed = await lz4CompressBlockAsync(data);
+    expect(lz4DecompressBlock(packed).equals(data)).toBe(true);
+  });
+
+  it("reports the worst-case compressed size", () => {
+    const random = Buffer.from(Array.from({ length: 4096 }, () => Math.floor(Math.random() * 256)));
+    expect(lz4Compress(random).length).toBeLessThanOrEqual(lz4CompressBound(random.length));
+    expect(lz4CompressBound(0)).toBeGreaterThan(0);
+  });
+
+  it("writes a little-endian size prefix for blocks", () => {
+    const packed = lz4CompressBlock(data);
+    expect(packed.readInt32LE(0)).toBe(data.length);
+    expect(lz4DecompressBlock(packed).equals(data)).toBe(true);
+  });
+
+  it("rejects block size prefixes beyond the maximum ratio", () => {
+    const packed = lz4CompressBlock(Buffer.from("tiny"));
+    packed.writeUInt32LE(0x7fffffff, 0);
+    expect(() => lz4DecompressBlock(packed)).toThrow("invalid LZ4 size prefix");
+  });
+
+  it("round-trips frames, including concatenated ones", () => {
+    const frame = lz4FrameCompress(data);
+    // LZ4 frame magic number
+    expect(frame.readUInt32LE(0)).toBe(0x184d2204);
+    expect(lz4FrameDecompress(frame).equals(data)).toBe(true);
+    const twice = Buffer.concat([data, data]);
+    expect(lz4FrameDecompress(Buffer.concat([frame, frame])).equals(twice)).toBe(true);
+  });
+
+  it("streams frames in arbitrary chunks", () => {
+    const encoder = new Lz4FrameEncoder();
+    const encoded: Buffer[] = [];
+    for (let i = 0; i < data.length; i += 1000) {
+      encoded.push(encoder.write(data.subarray(i, i + 1000)));
+    }
+    encoded.push(encoder.finish());
+    const frame = Buffer.concat(encoded);
+    expect(lz4FrameDecompress(frame).equals(data)).toBe(true);
+
+    const decoder = new Lz4FrameDecoder();
+    const decoded: Buffer[] = [];
+    for (let i = 0; i < frame.length; i += 7) {
+      decoded.push(decoder.write(frame.subarray(i, i + 7)));
+    }
+    decoded.push(decoder.finish());
+    expect(Buffer.concat(decoded).equals(data)).toBe(true);
+  });
+
+  it("rejects truncated frames", () => {
+    const frame = lz4FrameCompress(data);
+    const decoder = new Lz4FrameDecoder();
+    decoder.write(frame.subarray(0, frame.length - 4));
+    expect(() => decoder.finish()).toThrow("truncated LZ4 frame");
+  });
+});
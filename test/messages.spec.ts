@@ -0,0 +1,48 @@
+import { Socket } from "../nng";
+
+const wait = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+describe("Socket.messages", () => {
+  it("iterates messages with for await and disposes on break", async () => {
+    const url = "inproc://messages-iterate";
+    const server = new Socket();
+    server.listen(url);
+    const stream = Socket.messages(url);
+    await wait(50);
+    ["a", "b", "c"].forEach((m) => server.sendOnly(Buffer.from(m)));
+
+    const received: string[] = [];
+    for await (const msg of stream) {
+      received.push(msg.toString());
+      if (received.length === 3) break;
+    }
+    expect(received).toEqual(["a", "b", "c"]);
+    expect(stream.isClosed()).toBe(true);
+    expect(await stream.recv()).toBeNull();
+
+    server.close();
+  });
+
+  it("stops pulling from nng while the consumer is behind", async () => {
+    const url = "inproc://messages-backpressure";
+    const server = new Socket();
+    server.listen(url);
+    const stream = Socket.messages(url);
+    await wait(50);
+
+    let accepted = 0;
+    while (accepted < 1000 && server.trySend(Buffer.from(String(accepted)))) {
+      accepted++;
+      // 让接收线程有机会把消息搬进队列
+      if (accepted % 8 === 0) await wait(5);
+    }
+    expect(accepted).toBeGreaterThanOrEqual(16);
+    expect(accepted).toBeLessThan(1000);
+
+    for (let i = 0; i < accepted; i++) {
+      expect((await stream.recv())?.toString()).toBe(String(i));
+    }
+    stream.dispose();
+    server.close();
+  });
+});
@@ -0,0 +1,61 @@
+import { ReceivedMessage, Socket } from "../nng";
+
+describe("message metadata", () => {
+  it("attributes messages to pipes and replies with sendTo", async () => {
+    const url = "inproc://metadata";
+    const server = new Socket({ polyamorous: true, messageMetadata: true });
+    server.listen(url);
+    const disposable = server.onMessage((err, data, message) => {
+      if (err || !message) return;
+      server.sendTo(message.pipeId!, Buffer.from(`ack:${data.toString()}`));
+    });
+
+    const clients = [0, 1].map(() => {
+      const client = new Socket();
+      client.connect(url);
+      return client;
+    });
+    clients.forEach((client, i) => client.sendOnly(Buffer.from(String(i))));
+
+    // 回复由 onMessage 回调发出，需要让出事件循环
+    const replies = await Promise.all(clients.map((client) => client.recvAsync()));
+    expect(replies.map((reply) => reply.toString())).toEqual(["ack:0", "ack:1"]);
+
+    disposable.dispose();
+    clients.forEach((client) => client.close());
+    server.close();
+  });
+
+  it("passes a ReceivedMessage to recvMessage callbacks", async () => {
+    const url = "inproc://metadata-recv";
+    const server = new Socket();
+    server.listen(url);
+
+    const received = new Promise<ReceivedMessage>((resolve) => {
+      Socket.recvMessage(
+        url,
+        (err, _data, message) => {
+          if (!err && message) resolve(message);
+        },
+        { messageMetadata: true }
+      );
+    });
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    server.sendOnly(Buffer.from("hello"));
+
+    const message = await received;
+    expect(message.payload.toString()).toBe("hello");
+    expect(message.transport).toBe("inproc");
+    expect(message.pipeId).toBeGreaterThan(0);
+
+    server.close();
+  });
+
+  it("rejects sendTo for an unknown pipe", () => {
+    const socket = new Socket();
+    expect(() => socket.sendTo(12345, Buffer.from("x"))).toThrow(
+      "Pipe 12345 is not connected"
+    );
+    socket.close();
+  });
+});
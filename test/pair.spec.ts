@@ -0,0 +1,21 @@
+import { Socket } from "../nng";
+
+describe("pair1 send/recv", () => {
+  it("exchanges one-way messages in both directions", async () => {
+    const server = new Socket();
+    server.listen("inproc://pair-spec");
+    const client = new Socket({ recvTimeout: 1000 });
+    client.connect("inproc://pair-spec");
+
+    expect(client.tryRecv()).toBeNull();
+
+    client.sendOnly(Buffer.from("ping"));
+    expect(server.recv().toString()).toBe("ping");
+
+    server.sendOnly(Buffer.from("pushed"));
+    expect((await client.recvAsync()).toString()).toBe("pushed");
+
+    client.close();
+    server.close();
+  });
+});
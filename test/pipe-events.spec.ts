@@ -0,0 +1,35 @@
+import { PipeEventInfo, Socket } from "../nng";
+
+describe("pipe events", () => {
+  it("reports pipes being added and removed", async () => {
+    const url = "inproc://pipe-events";
+    const server = new Socket();
+    const added: PipeEventInfo[] = [];
+    const removed: PipeEventInfo[] = [];
+    server.on("pipeAdded", (pipe) => added.push(pipe));
+    server.on("pipeRemoved", (pipe) => removed.push(pipe));
+    server.listen(url);
+    expect(server.connected()).toBe(false);
+
+    const client = new Socket();
+    client.connect(url);
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    expect(server.connected()).toBe(true);
+    expect(client.connected()).toBe(true);
+    expect(added).toHaveLength(1);
+    expect(added[0].remoteAddress).toContain("inproc://");
+
+    client.close();
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    expect(server.connected()).toBe(false);
+    expect(removed.map((pipe) => pipe.id)).toEqual([added[0].id]);
+
+    server.close();
+  });
+
+  it("rejects unknown events", () => {
+    const socket = new Socket();
+    expect(() => socket.on("data" as any, () => {})).toThrow("Unknown event data");
+    socket.close();
+  });
+});
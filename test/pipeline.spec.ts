@@ -0,0 +1,40 @@
+import { Socket, SocketProtocol } from "../nng";
+
+describe("push/pull", () => {
+  it("delivers jobs from push to pull", async () => {
+    const url = "inproc://pipeline-spec";
+    const pull = new Socket({ protocol: SocketProtocol.Pull });
+    pull.listen(url);
+    const push = new Socket({ protocol: SocketProtocol.Push });
+    push.connect(url);
+
+    const received = new Promise<string[]>((resolve) => {
+      const jobs: string[] = [];
+      const disposable = pull.onMessage((err, data) => {
+        jobs.push(data.toString());
+        if (jobs.length === 2) {
+          disposable.dispose();
+          resolve(jobs);
+        }
+      });
+    });
+    push.sendOnly(Buffer.from("job-1"));
+    push.sendOnly(Buffer.from("job-2"));
+    expect(await received).toEqual(["job-1", "job-2"]);
+
+    push.close();
+    pull.close();
+  });
+
+  it("rejects receiving on push and sending on pull", () => {
+    const push = new Socket({ protocol: SocketProtocol.Push });
+    expect(() => push.recv()).toThrow("recv is not supported on a push socket");
+    push.close();
+
+    const pull = new Socket({ protocol: SocketProtocol.Pull });
+    expect(() => pull.sendOnly(Buffer.from("job"))).toThrow(
+      "sendOnly is not supported on a pull socket"
+    );
+    pull.close();
+  });
+});
@@ -0,0 +1,17 @@
+import { Socket, SocketProtocol } from "../nng";
+
+describe("protocol validation", () => {
+  it("rejects request/reply calls on one-way sockets", () => {
+    const socket = new Socket({ protocol: SocketProtocol.Push });
+    expect(() => socket.send(Buffer.from("job"))).toThrow(
+      "send is not supported on a push socket (supported: pair0, pair1, req)"
+    );
+    socket.close();
+  });
+
+  it("rejects subscriptions on non-sub sockets", () => {
+    const socket = new Socket();
+    expect(() => socket.subscribe("topic")).toThrow(/not supported on a pair1 socket/);
+    socket.close();
+  });
+});
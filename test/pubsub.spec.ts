@@ -0,0 +1,35 @@
+import { Socket, SocketProtocol } from "../nng";
+
+describe("pub/sub", () => {
+  it("delivers only subscribed topics", async () => {
+    const url = "inproc://pubsub-spec";
+    const pub = new Socket({ protocol: SocketProtocol.Pub });
+    pub.listen(url);
+
+    const received: string[] = [];
+    const disposable = Socket.recvMessage(
+      url,
+      (err, data) => {
+        if (!err) received.push(data.toString());
+      },
+      { protocol: SocketProtocol.Sub }
+    );
+    disposable.subscribe("news:");
+
+    // 订阅建立需要一点时间，持续发布直到收到为止
+    await new Promise<void>((resolve) => {
+      const timer = setInterval(() => {
+        pub.publish(Buffer.from("sport:1"));
+        pub.publish(Buffer.from("news:1"));
+        if (received.length > 0) {
+          clearInterval(timer);
+          resolve();
+        }
+      }, 10);
+    });
+
+    expect(received.every((msg) => msg.startsWith("news:"))).toBe(true);
+    disposable.dispose();
+    pub.close();
+  });
+});
@@ -0,0 +1,144 @@
+import { Socket, SocketProtocol } from "../nng";
+
+describe("startReceiving", () => {
+  it("sends and receives on the same pair1 connection", async () => {
+    const url = "inproc://receiving-duplex";
+    const server = new Socket({ protocol: SocketProtocol.Pair1 });
+    server.listen(url);
+    const client = new Socket({ protocol: SocketProtocol.Pair1 });
+    client.connect(url);
+
+    // 服务端在同一个 Socket 上收到消息后直接回复
+    server.on("message", (data) => server.sendOnly(Buffer.from(`pong:${data.toString()}`)));
+    server.startReceiving();
+
+    const replies: string[] = [];
+    const done = new Promise<void>((resolve) => {
+      client.on("message", (data) => {
+        replies.push(data.toString());
+        if (replies.length === 2) resolve();
+      });
+    });
+    client.startReceiving();
+    expect(client.isReceiving()).toBe(true);
+
+    client.sendOnly(Buffer.from("1"));
+    client.sendOnly(Buffer.from("2"));
+    await done;
+    expect(replies).toEqual(["pong:1", "pong:2"]);
+
+    client.close();
+    server.close();
+  });
+
+  it("emits close when receiving stops", async () => {
+    const socket = new Socket({ protocol: SocketProtocol.Pair1, recvTimeout: 100 });
+    socket.listen("inproc://receiving-close");
+    const closed = new Promise<void>((resolve) => socket.on("close", resolve));
+    socket.startReceiving();
+    expect(() => socket.startReceiving()).toThrow("Already receiving");
+
+    socket.stopReceiving();
+    await closed;
+    expect(socket.isReceiving()).toBe(false);
+    socket.close();
+  });
+
+  it("hands messages sent after a restart to the new receiver", async () => {
+    const url = "inproc://receiving-restart";
+    // 默认的 recvTimeout 较长，stopReceiving 需要立即取消正在进行的接收
+    const server = new Socket({ protocol: SocketProtocol.Pair1 });
+    server.listen(url);
+    const client = new Socket({ protocol: SocketProtocol.Pair1 });
+    client.connect(url);
+    const received: string[] = [];
+    server.on("message", (data) => received.push(data.toString()));
+
+    server.startReceiving();
+    server.stopReceiving();
+    server.startReceiving();
+    client.sendOnly(Buffer.from("after restart"));
+    await new Promise((resolve) => setTimeout(resolve, 100));
+    expect(received).toEqual(["after restart"]);
+
+    client.close();
+    server.close();
+  });
+
+  it("stops calling listeners removed with off", async () => {
+    const url = "inproc://receiving-off";
+    const server = new Socket({ protocol: SocketProtocol.Pair1 });
+    server.listen(url);
+    const client = new Socket({ protocol: SocketProtocol.Pair1 });
+    client.connect(url);
+
+    const removed: string[] = [];
+    const kept: string[] = [];
+    const listener = (data: Buffer) => removed.push(data.toString());
+    server.on("message", listener);
+    server.on("message", (data) => kept.push(data.toString()));
+    server.startReceiving();
+
+    client.sendOnly(Buffer.from("1"));
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    server.off("message", listener);
+    client.sendOnly(Buffer.from("2"));
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    expect(removed).toEqual(["1"]);
+    expect(kept).toEqual(["1", "2"]);
+    expect(server.listenerCount("message")).toBe(1);
+
+    client.close();
+    server.close();
+  });
+
+  it("rejects unknown events and send-only protocols", () => {
+    const push = new Socket({ protocol: SocketProtocol.Push });
+    expect(() => push.on("data" as any, () => {})).toThrow("Unknown event data");
+    expect(() => push.startReceiving()).toThrow("startReceiving is not supported on a push socket");
+    push.close();
+  });
+
+  it("shares one receiver between startReceiving and onMessage", async () => {
+    const socket = new Socket({ protocol: SocketProtocol.Pair1 });
+    socket.listen("inproc://receiving-slot");
+
+    const disposable = socket.onMessage(() => {});
+    expect(socket.isReceiving()).toBe(true);
+    expect(() => socket.startReceiving()).toThrow("Already receiving");
+    expect(() => socket.onMessage(() => {})).toThrow("Already receiving");
+    expect(() => socket.tryRecv()).toThrow("tryRecv is not available while startReceiving() or onMessage() is receiving");
+    expect(() => socket.recvAsync()).toThrow("recvAsync is not available");
+
+    disposable.dispose();
+    expect(socket.isReceiving()).toBe(false);
+    expect(socket.tryRecv()).toBeNull();
+    socket.startReceiving();
+    expect(() => socket.recv()).toThrow("recv is not available");
+    socket.close();
+  });
+
+  it("stops onMessage on stopReceiving and close", async () => {
+    const url = "inproc://receiving-slot-stop";
+    const server = new Socket({ protocol: SocketProtocol.Pair1 });
+    server.listen(url);
+    const client = new Socket({ protocol: SocketProtocol.Pair1 });
+    client.connect(url);
+    const received: string[] = [];
+    server.onMessage((err, data) => {
+      if (!err) received.push(data.toString());
+    });
+
+    server.stopReceiving();
+    expect(server.isReceiving()).toBe(false);
+    client.sendOnly(Buffer.from("left in the socket"));
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    expect(received).toEqual([]);
+    expect(server.recv().toString()).toBe("left in the socket");
+
+    server.onMessage(() => {});
+    server.close();
+    expect(server.isReceiving()).toBe(false);
+    client.close();
+  });
+});
@@ -0,0 +1,144 @@
+import { ReconnectState, ReconnectStatus, Socket } from "../nng";
+
+function waitFor(statuses: ReconnectStatus[], state: ReconnectState) {
+  return new Promise<void>((resolve) => {
+    const timer = setInterval(() => {
+      if (statuses.some((s) => s.state === state)) {
+        clearInterval(timer);
+        resolve();
+      }
+    }, 10);
+  });
+}
+
+describe("reconnect", () => {
+  it("keeps retrying until the peer comes up", async () => {
+    const url = "inproc://reconnect-late-listener";
+    const statuses: ReconnectStatus[] = [];
+    const received: string[] = [];
+    const disposable = Socket.recvMessage(
+      url,
+      (err, data) => {
+        if (!err) received.push(data.toString());
+      },
+      { reconnect: { initialDelay: 20, maxDelay: 50 } },
+      (status) => statuses.push(status)
+    );
+
+    await waitFor(statuses, ReconnectState.Reconnecting);
+    expect(disposable.isConnectionAlive()).toBe(false);
+
+    const server = new Socket();
+    server.listen(url);
+    await waitFor(statuses, ReconnectState.Connected);
+    expect(statuses[0].state).toBe(ReconnectState.Connecting);
+    expect(disposable.isConnectionAlive()).toBe(true);
+
+    server.sendOnly(Buffer.from("hello"));
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    expect(received).toEqual(["hello"]);
+
+    disposable.dispose();
+    server.close();
+  });
+
+  it("gives up after maxAttempts", async () => {
+    const statuses: ReconnectStatus[] = [];
+    const gaveUp = new Promise<Error>((resolve) => {
+      Socket.recvMessage(
+        "inproc://reconnect-nobody",
+        (err) => {
+          if (err) resolve(err);
+        },
+        { reconnect: { initialDelay: 10, maxAttempts: 2 } },
+        (status) => statuses.push(status)
+      );
+    });
+
+    expect((await gaveUp).message).toContain("Reconnect gave up after 2 attempts");
+    expect(statuses.map((s) => s.state)).toEqual([
+      ReconnectState.Connecting,
+      ReconnectState.Reconnecting,
+      ReconnectState.Reconnecting,
+      ReconnectState.GaveUp,
+    ]);
+  });
+
+  it("reconnects on heartbeat timeout with the same disposable", async () => {
+    const url = "inproc://reconnect-heartbeat";
+    let server = new Socket({ heartbeatAutoReply: true });
+    server.listen(url);
+    let serverDisposable = server.onMessage(() => {});
+
+    const statuses: ReconnectStatus[] = [];
+    const received: string[] = [];
+    const disposable = Socket.recvMessage(
+      url,
+      (err, data) => {
+        if (!err) received.push(data.toString());
+      },
+      {
+        recvTimeout: 50,
+        enableHeartbeat: true,
+        heartbeatInterval: 30,
+        heartbeatMaxMissed: 2,
+        reconnect: { initialDelay: 20, maxDelay: 50 },
+      },
+      (status) => statuses.push(status)
+    );
+    await waitFor(statuses, ReconnectState.Connected);
+
+    serverDisposable.dispose();
+    server.close();
+    await waitFor(statuses, ReconnectState.Reconnecting);
+
+    statuses.length = 0;
+    server = new Socket({ heartbeatAutoReply: true });
+    server.listen(url);
+    serverDisposable = server.onMessage(() => {});
+    await waitFor(statuses, ReconnectState.Connected);
+
+    server.sendOnly(Buffer.from("back"));
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    expect(received).toEqual(["back"]);
+
+    disposable.dispose();
+    serverDisposable.dispose();
+    server.close();
+  });
+
+  it("reconnects when the peer goes away without heartbeat", async () => {
+    const url = "inproc://reconnect-no-heartbeat";
+    let server = new Socket();
+    server.listen(url);
+
+    const statuses: ReconnectStatus[] = [];
+    const received: string[] = [];
+    const disposable = Socket.recvMessage(
+      url,
+      (err, data) => {
+        if (!err) received.push(data.toString());
+      },
+      // recvTimeout 远大于测试时间，只能靠连接断开的通知发现对端已经离开
+      { recvTimeout: 60_000, reconnect: { initialDelay: 20, maxDelay: 50 } },
+      (status) => statuses.push(status)
+    );
+    await waitFor(statuses, ReconnectState.Connected);
+
+    server.close();
+    await waitFor(statuses, ReconnectState.Reconnecting);
+    expect(disposable.isConnectionAlive()).toBe(false);
+
+    statuses.length = 0;
+    server = new Socket();
+    server.listen(url);
+    await waitFor(statuses, ReconnectState.Connected);
+
+    server.sendOnly(Buffer.from("back"));
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    expect(received).toEqual(["back"]);
+
+    disposable.dispose();
+    server.close();
+  });
+});
@@ -0,0 +1,114 @@
+import { Socket, SocketProtocol } from "../nng";
+
+describe("req/rep", () => {
+  const url = "inproc://reqrep-spec";
+  let server: Socket;
+  let client: Socket;
+
+  beforeEach(() => {
+    server = new Socket({ protocol: SocketProtocol.Rep });
+    server.listen(url);
+    client = new Socket({ protocol: SocketProtocol.Req, recvTimeout: 2000 });
+    client.connect(url);
+  });
+
+  afterEach(() => {
+    client.close();
+    server.close();
+  });
+
+  it("replies with the value returned by a sync handler", async () => {
+    const disposable = server.serve((err, req) => Buffer.concat([Buffer.from("echo:"), req]));
+    const reply = await client.sendAsync(Buffer.from("hello"));
+    expect(reply.toString()).toBe("echo:hello");
+    disposable.dispose();
+  });
+
+  it("drops requests whose handler throws synchronously and keeps serving", async () => {
+    const disposable = server.serve((err, req) => {
+      if (req.toString() === "bad") throw new Error("bad request");
+      return req;
+    });
+    const impatient = new Socket({ protocol: SocketProtocol.Req, recvTimeout: 200 });
+    impatient.connect(url);
+    await expect(impatient.sendAsync(Buffer.from("bad"))).rejects.toThrow("Recv rpc failed");
+    expect((await client.sendAsync(Buffer.from("good"))).toString()).toBe("good");
+    impatient.close();
+    disposable.dispose();
+  });
+
+  it("keeps the event loop running while sendAsync waits for the reply", async () => {
+    const disposable = server.serve(async (err, req) => {
+      await new Promise((resolve) => setTimeout(resolve, 100));
+      return req;
+    });
+    let ticks = 0;
+    const timer = setInterval(() => ticks++, 10);
+    const reply = await client.sendAsync(Buffer.from("slow"));
+    clearInterval(timer);
+    expect(reply.toString()).toBe("slow");
+    expect(ticks).toBeGreaterThan(2);
+    disposable.dispose();
+  });
+
+  it("rejects sendAsync when no reply arrives before recvTimeout", async () => {
+    const impatient = new Socket({ protocol: SocketProtocol.Req, recvTimeout: 200 });
+    impatient.connect(url);
+    let pending: Promise<Buffer> | undefined;
+    expect(() => (pending = impatient.sendAsync(Buffer.from("ignored")))).not.toThrow();
+    await expect(pending).rejects.toThrow("Recv rpc failed");
+    impatient.close();
+  });
+
+  it("rejects sendAsync on a closed socket", async () => {
+    const closed = new Socket({ protocol: SocketProtocol.Req });
+    closed.close();
+    let pending: Promise<Buffer> | undefined;
+    expect(() => (pending = closed.sendAsync(Buffer.from("late")))).not.toThrow();
+    await expect(pending).rejects.toThrow();
+  });
+
+  it("matches replies to concurrent requests", async () => {
+    const disposable = server.serve(async (err, req) => {
+      await new Promise((resolve) => setTimeout(resolve, Number(req.toString()) * 10));
+      return req;
+    });
+    const replies = await Promise.all(
+      ["3", "1", "2"].map((n) => client.sendAsync(Buffer.from(n)))
+    );
+    expect(replies.map((r) => r.toString())).toEqual(["3", "1", "2"]);
+    disposable.dispose();
+  });
+
+  it("gives every concurrent sendAsync caller on one Req socket its own reply", async () => {
+    // 回复的顺序与请求顺序无关，每个请求由各自的 Context 匹配回复
+    const disposable = server.serve(async (err, req) => {
+      await new Promise((resolve) => setTimeout(resolve, Math.random() * 30));
+      return Buffer.from(`reply:${req.toString()}`);
+    }, 8);
+    const ids = Array.from({ length: 20 }, (_, i) => String(i));
+    const replies = await Promise.all(ids.map((id) => client.sendAsync(Buffer.from(id))));
+    expect(replies.map((r) => r.toString())).toEqual(ids.map((id) => `reply:${id}`));
+    disposable.dispose();
+  });
+
+  it("stops serving as soon as dispose returns, even with a long recvTimeout", async () => {
+    const slow = new Socket({ protocol: SocketProtocol.Rep, recvTimeout: 60_000 });
+    slow.listen("inproc://reqrep-spec-dispose");
+    const handled: string[] = [];
+    const disposable = slow.serve((err, req) => {
+      handled.push(req.toString());
+      return req;
+    });
+    const started = Date.now();
+    disposable.dispose();
+    expect(Date.now() - started).toBeLessThan(1000);
+
+    const late = new Socket({ protocol: SocketProtocol.Req, recvTimeout: 200 });
+    late.connect("inproc://reqrep-spec-dispose");
+    await expect(late.sendAsync(Buffer.from("late"))).rejects.toThrow("Recv rpc failed");
+    expect(handled).toEqual([]);
+    late.close();
+    slow.close();
+  });
+});
@@ -0,0 +1,24 @@
+import { Socket, SocketProtocol } from "../nng";
+
+describe("surveyor/respondent", () => {
+  it("collects all responses before the deadline", async () => {
+    const url = "inproc://survey-spec";
+    const surveyor = new Socket({ protocol: SocketProtocol.Surveyor });
+    surveyor.listen(url);
+
+    const respondents = ["a", "b"].map((name) => {
+      const socket = new Socket({ protocol: SocketProtocol.Respondent });
+      socket.connect(url);
+      return { socket, disposable: socket.serve(() => Buffer.from(name)) };
+    });
+
+    const responses = await surveyor.survey(Buffer.from("who?"), { deadlineMs: 300 });
+    expect(responses.map((r) => r.toString()).sort()).toEqual(["a", "b"]);
+
+    for (const { socket, disposable } of respondents) {
+      disposable.dispose();
+      socket.close();
+    }
+    surveyor.close();
+  });
+});
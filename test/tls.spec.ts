@@ -0,0 +1,104 @@
+import { readFileSync } from "fs";
+import { join } from "path";
+import { Socket, SocketProtocol, TlsAuthMode } from "../nng";
+
+// 仅用于测试的自签名证书（openssl 生成，有效期 100 年），server / client 证书由 ca.pem 签发，
+// 包含 localhost 和 127.0.0.1
+const fixture = (name: string) => readFileSync(join(__dirname, "fixtures", "tls", name), "utf8");
+const ca = fixture("ca.pem");
+const server = { cert: fixture("server.pem"), key: fixture("server.key") };
+const client = { cert: fixture("client.pem"), key: fixture("client.key") };
+
+// 没有以 tls feature 编译时跳过；npm run test:tls 以 tls feature 构建后运行，这时 TLS 不可用算作失败
+const tlsRequired = process.env.npm_lifecycle_event === "test:tls";
+const tlsEnabled = (() => {
+  const probe = new Socket();
+  try {
+    probe.listen("tls+tcp://127.0.0.1:0");
+    return true;
+  } catch (e) {
+    return !(e as Error).message.includes("not enabled in this build");
+  } finally {
+    probe.close();
+  }
+})();
+
+it.runIf(tlsRequired)("is enabled in this build", () => {
+  expect(tlsEnabled).toBe(true);
+});
+
+describe.runIf(tlsEnabled)("tls", () => {
+  it("sends requests over tls+tcp with a self-signed CA", async () => {
+    const url = "tls+tcp://127.0.0.1:18761";
+    const rep = new Socket({ protocol: SocketProtocol.Rep, tls: server });
+    rep.listen(url);
+    const req = new Socket({ protocol: SocketProtocol.Req, tls: { ca } });
+    req.connect(url);
+
+    const disposable = rep.serve((err, data) => Buffer.from(`tls:${data.toString()}`));
+    expect((await req.sendAsync(Buffer.from("ping"))).toString()).toBe("tls:ping");
+
+    disposable.dispose();
+    req.close();
+    rep.close();
+  });
+
+  it("requires client certificates when authMode is required", async () => {
+    const url = "tls+tcp://127.0.0.1:18762";
+    const rep = new Socket({
+      protocol: SocketProtocol.Rep,
+      tls: { ...server, ca, authMode: TlsAuthMode.Required },
+    });
+    rep.listen(url);
+
+    const anonymous = new Socket({ protocol: SocketProtocol.Req, tls: { ca } });
+    expect(() => anonymous.connect(url)).toThrow(`Connect ${url} failed`);
+    anonymous.close();
+
+    const trusted = new Socket({ protocol: SocketProtocol.Req, tls: { ca, ...client } });
+    trusted.connect(url);
+    const disposable = rep.serve((err, data) => data);
+    expect((await trusted.sendAsync(Buffer.from("hi"))).toString()).toBe("hi");
+
+    disposable.dispose();
+    trusted.close();
+    rep.close();
+  });
+
+  it("explains handshake failures", () => {
+    const url = "tls+tcp://127.0.0.1:18763";
+    const rep = new Socket({ protocol: SocketProtocol.Rep, tls: server });
+    rep.listen(url);
+
+    const untrusted = new Socket({ protocol: SocketProtocol.Req, tls: { ca: fixture("other-ca.pem") } });
+    expect(() => untrusted.connect(url)).toThrow("TLS handshake failed");
+    untrusted.close();
+
+    const wrongName = new Socket({
+      protocol: SocketProtocol.Req,
+      tls: { ca, serverName: "example.com" },
+    });
+    expect(() => wrongName.connect(url)).toThrow("TLS handshake failed");
+    wrongName.close();
+    rep.close();
+  });
+
+  it("validates the tls options", () => {
+    const socket = new Socket();
+    expect(() => socket.listen("tls+tcp://127.0.0.1:18764")).toThrow(
+      "requires tls options with cert and key"
+    );
+    socket.close();
+
+    // 没有系统的受信任证书可用，校验服务端证书时必须提供 ca
+    for (const tls of [undefined, {}, { authMode: TlsAuthMode.Required }]) {
+      const noCa = new Socket({ tls });
+      expect(() => noCa.connect("tls+tcp://127.0.0.1:18764")).toThrow("requires tls.ca");
+      noCa.close();
+    }
+
+    const invalid = new Socket({ tls: { cert: "not a cert", key: "not a key" } });
+    expect(() => invalid.listen("tls+tcp://127.0.0.1:18764")).toThrow("Invalid TLS cert or key");
+    invalid.close();
+  });
+});
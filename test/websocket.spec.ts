@@ -0,0 +1,77 @@
+import { PipeEventInfo, Socket, SocketProtocol, WebSocketMessageType } from "../nng";
+
+describe("websocket", () => {
+  it("sends requests over ws://", async () => {
+    const url = "ws://127.0.0.1:18771/rpc";
+    const rep = new Socket({ protocol: SocketProtocol.Rep });
+    rep.listen(url);
+    const req = new Socket({ protocol: SocketProtocol.Req });
+    req.connect(url);
+
+    const disposable = rep.serve((err, data) => Buffer.from(`ws:${data.toString()}`));
+    expect((await req.sendAsync(Buffer.from("ping"))).toString()).toBe("ws:ping");
+
+    disposable.dispose();
+    req.close();
+    rep.close();
+  });
+
+  it("exposes the handshake request in pipeAdded", async () => {
+    const server = new Socket({
+      protocol: SocketProtocol.Pair1,
+      webSocket: { path: "/gateway", responseHeaders: { "X-Gateway": "node" } },
+    });
+    const added = new Promise<PipeEventInfo>((resolve) => server.on("pipeAdded", resolve));
+    server.listen("ws://127.0.0.1:18772");
+
+    const client = new Socket({
+      protocol: SocketProtocol.Pair1,
+      webSocket: { path: "gateway", requestHeaders: { Authorization: "Bearer token" } },
+    });
+    client.connect("ws://127.0.0.1:18772/ignored");
+
+    const pipe = await added;
+    expect(pipe.requestUri).toBe("/gateway");
+    expect(pipe.requestHeaders?.Authorization).toBe("Bearer token");
+
+    client.close();
+    server.close();
+  });
+
+  it("routes listeners on the same port by path", async () => {
+    const chat = new Socket({ protocol: SocketProtocol.Pull });
+    chat.listen("ws://127.0.0.1:18773/chat");
+    const rpc = new Socket({ protocol: SocketProtocol.Pull });
+    rpc.listen("ws://127.0.0.1:18773/rpc");
+
+    const push = new Socket({ protocol: SocketProtocol.Push });
+    push.connect("ws://127.0.0.1:18773/rpc");
+    push.sendOnly(Buffer.from("to rpc"));
+    expect((await rpc.recvAsync()).toString()).toBe("to rpc");
+
+    push.close();
+    rpc.close();
+    chat.close();
+  });
+
+  it("uses text frames when messageType is text", async () => {
+    const url = "ws://127.0.0.1:18774/text";
+    const webSocket = { messageType: WebSocketMessageType.Text };
+    const server = new Socket({ protocol: SocketProtocol.Pair1, webSocket });
+    server.listen(url);
+    const client = new Socket({ protocol: SocketProtocol.Pair1, webSocket });
+    client.connect(url);
+
+    client.sendOnly(Buffer.from("你好"));
+    expect((await server.recvAsync()).toString()).toBe("你好");
+
+    client.close();
+    server.close();
+  });
+
+  it("rejects invalid headers", () => {
+    const socket = new Socket({ webSocket: { requestHeaders: { "X-Bad": "a\r\nInjected: b" } } });
+    expect(() => socket.connect("ws://127.0.0.1:18775")).toThrow("Invalid WebSocket header");
+    socket.close();
+  });
+});
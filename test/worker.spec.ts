@@ -0,0 +1,82 @@
+import { join } from "path";
+import { Worker } from "worker_threads";
+import { PipeEventInfo, Socket, SocketProtocol } from "../nng";
+
+const addon = join(__dirname, "..", "nng.js");
+
+// 在 Worker 中加载同一个 addon 并执行 body，body 就绪后通过 parentPort.postMessage("ready") 通知
+const startWorker = async (body: string) => {
+  const worker = new Worker(
+    `const { parentPort } = require("worker_threads");
+     const { Socket } = require(${JSON.stringify(addon)});
+     ${body}`,
+    { eval: true }
+  );
+  await new Promise<void>((resolve, reject) => {
+    worker.once("message", () => resolve());
+    worker.once("error", reject);
+  });
+  return worker;
+};
+
+describe("inproc across worker_threads", () => {
+  it("sends and receives between the main thread and a worker", async () => {
+    const worker = await startWorker(`
+      const socket = new Socket({ protocol: "pair1" });
+      socket.listen("inproc://worker-pair");
+      parentPort.postMessage("ready");
+      for (let i = 0; i < 3; i++) {
+        socket.sendOnly(Buffer.from(socket.recv().toString().toUpperCase()));
+      }
+      socket.close();
+    `);
+
+    const socket = new Socket({ protocol: SocketProtocol.Pair1 });
+    socket.connect("inproc://worker-pair");
+    for (const word of ["a", "b", "c"]) {
+      socket.sendOnly(Buffer.from(word));
+      expect((await socket.recvAsync()).toString()).toBe(word.toUpperCase());
+    }
+
+    socket.close();
+    await worker.terminate();
+  });
+
+  it("serves requests from a worker", async () => {
+    const worker = await startWorker(`
+      const server = new Socket({ protocol: "rep" });
+      server.listen("inproc://worker-serve");
+      server.serve((err, req) => Buffer.from(String(Number(req.toString()) * 2)));
+      parentPort.postMessage("ready");
+    `);
+
+    const client = new Socket({ protocol: SocketProtocol.Req });
+    client.connect("inproc://worker-serve");
+    const replies = await Promise.all([1, 2, 3].map((n) => client.sendAsync(Buffer.from(String(n)))));
+    expect(replies.map((r) => r.toString())).toEqual(["2", "4", "6"]);
+
+    client.close();
+    await worker.terminate();
+  });
+
+  it("closes the worker's sockets when the worker exits", async () => {
+    const worker = await startWorker(`
+      // 接收线程每个 recvTimeout 检查一次 Worker 是否已经退出
+      const socket = new Socket({ protocol: "pair1", recvTimeout: 100 });
+      socket.listen("inproc://worker-exit");
+      socket.on("message", () => {});
+      socket.startReceiving();
+      parentPort.postMessage("ready");
+    `);
+
+    const socket = new Socket({ protocol: SocketProtocol.Pair1 });
+    const removed = new Promise<PipeEventInfo>((resolve) => socket.on("pipeRemoved", resolve));
+    socket.connect("inproc://worker-exit");
+    expect(socket.connected()).toBe(true);
+
+    await worker.terminate();
+    await removed;
+    expect(socket.connected()).toBe(false);
+    socket.close();
+  });
+});
@@ -0,0 +1,62 @@
+import {
+  ZstdDictionary,
+  lz4CompressBlock,
+  zstdCompress,
+  zstdCompressAsync,
+  zstdCompressWithDictionary,
+  zstdDecompress,
+  zstdDecompressWithDictionary,
+  zstdTrainDictionary,
+} from "../nng";
+
+const data = Buffer.from("nng ".repeat(10000) + "end");
+
+const message = (i: number) =>
+  Buffer.from(
+    JSON.stringify({
+      id: i,
+      type: i % 3 === 0 ? "order.created" : "order.updated",
+      customer: { name: `customer-${i % 17}`, region: ["cn-north", "cn-east"][i % 2] },
+      amount: (i * 37) % 1000,
+      status: "pending",
+    })
+  );
+
+describe("zstd", () => {
+  it("round-trips frames with and without maxSize", () => {
+    const compressed = zstdCompress(data);
+    expect(compressed.length).toBeLessThan(data.length);
+    expect(zstdDecompress(compressed).equals(data)).toBe(true);
+    expect(zstdDecompress(compressed, data.length).equals(data)).toBe(true);
+    expect(() => zstdDecompress(compressed, 16)).toThrow("Decompression failed");
+  });
+
+  it("supports compression levels and async compression", async () => {
+    const fast = zstdCompress(data, -5);
+    const best = zstdCompress(data, 100);
+    expect(zstdDecompress(fast).equals(data)).toBe(true);
+    expect(zstdDecompress(best).equals(data)).toBe(true);
+    expect(zstdDecompress(await zstdCompressAsync(data, 9)).equals(data)).toBe(true);
+  });
+
+  it("compresses small similar messages better with a trained dictionary", () => {
+    const samples = Array.from({ length: 1000 }, (_, i) => message(i));
+    const dictionary = zstdTrainDictionary(samples, 4096);
+    expect(dictionary.length).toBeLessThanOrEqual(4096);
+
+    const input = message(5000);
+    const packed = zstdCompressWithDictionary(input, dictionary);
+    expect(packed.length).toBeLessThan(zstdCompress(input).length);
+    expect(packed.length).toBeLessThan(lz4CompressBlock(input).length);
+    expect(zstdDecompressWithDictionary(packed, dictionary).equals(input)).toBe(true);
+
+    const dict = new ZstdDictionary(dictionary, 5);
+    const reused = dict.compress(input);
+    expect(dict.decompress(reused).equals(input)).toBe(true);
+    expect(dict.decompress(packed).equals(input)).toBe(true);
+  });
+
+  it("reports training failures", () => {
+    expect(() => zstdTrainDictionary([Buffer.from("a")])).toThrow("Train dictionary failed");
+  });
+});